@@ -0,0 +1,126 @@
+//! Prompt latency on a synthetic large repository.
+//!
+//! Compares the single-pass status scan used by `get_git_repo_state` with the
+//! previous approach of scanning the working tree once per counter.
+//!
+//! Run with `cargo bench --bench status_scan`. The repository size can be changed
+//! with `GPROMPT_BENCH_FILES` (default 20000).
+
+use git2::{Repository, Status, StatusOptions};
+use gprompt::services::git_status::{get_git_repo_state, ScanOptions};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const ITERATIONS: usize = 10;
+
+fn git(path: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Creates a repository with `files` committed files spread over 100 directories,
+/// with 1% of them modified, 1% staged and 1% untracked
+fn create_large_repo(path: &Path, files: usize) {
+    git(path, &["init", "-q"]);
+    git(path, &["config", "user.email", "bench@example.com"]);
+    git(path, &["config", "user.name", "Bench"]);
+    for i in 0..files {
+        let dir = path.join(format!("dir{}", i % 100));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("file{i}.txt")), format!("content {i}\n")).unwrap();
+    }
+    git(path, &["add", "-A"]);
+    git(path, &["commit", "-q", "-m", "initial"]);
+
+    let mut staged = vec!["add".to_string()];
+    for i in (0..files.saturating_sub(1)).step_by(100) {
+        let dir = path.join(format!("dir{}", i % 100));
+        fs::write(dir.join(format!("file{i}.txt")), "modified\n").unwrap();
+        fs::write(dir.join(format!("untracked{i}.txt")), "untracked\n").unwrap();
+        let file = format!("dir{}/file{}.txt", (i + 1) % 100, i + 1);
+        fs::write(path.join(&file), "staged\n").unwrap();
+        staged.push(file);
+    }
+    let staged: Vec<&str> = staged.iter().map(String::as_str).collect();
+    git(path, &staged);
+}
+
+/// The previous implementation: one status scan per counter
+fn three_pass(repo: &Repository) -> (usize, usize, usize) {
+    let count = |flags: Status| {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true);
+        repo.statuses(Some(&mut opts))
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.status().intersects(flags))
+            .count()
+    };
+    let staged = count(
+        Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_TYPECHANGE
+            | Status::INDEX_RENAMED,
+    );
+    let unstaged = count(
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+    );
+    let untracked = count(Status::WT_NEW);
+    (staged, unstaged, untracked)
+}
+
+fn median<F: FnMut()>(mut f: F) -> Duration {
+    // Warm up the filesystem cache and the index
+    f();
+    let mut samples: Vec<Duration> = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    samples.sort();
+    samples[ITERATIONS / 2]
+}
+
+fn main() {
+    let files = std::env::var("GPROMPT_BENCH_FILES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20_000);
+
+    let tmp = TempDir::new().unwrap();
+    println!("creating repository with {files} files...");
+    create_large_repo(tmp.path(), files);
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        three_pass(&repo),
+        (state.staged, state.unstaged, state.untracked),
+        "single-pass counts differ from three-pass counts"
+    );
+
+    let old = median(|| {
+        three_pass(&repo);
+    });
+    let new = median(|| {
+        get_git_repo_state(tmp.path(), &ScanOptions::default());
+    });
+
+    println!("three status scans:       {old:>10.2?}");
+    println!("get_git_repo_state:       {new:>10.2?}");
+    println!(
+        "speedup:                  {:>9.2}x",
+        old.as_secs_f64() / new.as_secs_f64()
+    );
+}
@@ -0,0 +1,126 @@
+// src/cli.rs
+//! Command-line argument parsing for the gprompt binary.
+
+use crate::models::shell::{Shell, ShellState};
+use std::str::FromStr;
+
+/// What the binary has been asked to do
+#[derive(Debug)]
+pub enum Command {
+    /// Render the prompt (the default)
+    Prompt(PromptArgs),
+
+    /// Print the integration script for a shell (`gprompt init <shell>`)
+    Init(Shell),
+
+    /// Print the repository state as JSON (`gprompt status --json`)
+    Status,
+
+    /// Run the background daemon that caches repository state (`gprompt daemon`)
+    Daemon,
+}
+
+/// Options for rendering the prompt
+#[derive(Debug, Default)]
+pub struct PromptArgs {
+    /// Render `right_format` instead of `format`
+    pub right: bool,
+
+    /// Shell the output is escaped for
+    pub shell: Shell,
+
+    /// Session details passed in by the shell integration
+    pub shell_state: ShellState,
+}
+
+/// Parses command-line arguments (without the program name)
+///
+/// # Returns
+/// * `Ok(Command)` - The parsed command
+/// * `Err(String)` - A message describing the first invalid argument
+pub fn parse_args<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+
+    if args.peek().map(String::as_str) == Some("init") {
+        args.next();
+        let name = args
+            .next()
+            .ok_or("init requires a shell (bash, zsh or fish)")?;
+        let shell = match Shell::from_name(&name) {
+            Some(Shell::Plain) | None => {
+                return Err(format!(
+                    "unknown shell \"{name}\" (expected bash, zsh or fish)"
+                ))
+            }
+            Some(shell) => shell,
+        };
+        if let Some(extra) = args.next() {
+            return Err(format!("unexpected argument \"{extra}\""));
+        }
+        return Ok(Command::Init(shell));
+    }
+
+    if args.peek().map(String::as_str) == Some("status") {
+        args.next();
+        return match args.next().as_deref() {
+            Some("--json") => match args.next() {
+                Some(extra) => Err(format!("unexpected argument \"{extra}\"")),
+                None => Ok(Command::Status),
+            },
+            Some(other) => Err(format!("unexpected argument \"{other}\"")),
+            None => Err("status requires --json".to_string()),
+        };
+    }
+
+    if args.peek().map(String::as_str) == Some("daemon") {
+        args.next();
+        if let Some(extra) = args.next() {
+            return Err(format!("unexpected argument \"{extra}\""));
+        }
+        return Ok(Command::Daemon);
+    }
+
+    let mut parsed = PromptArgs::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{flag} requires a value"))
+        };
+        match flag.as_str() {
+            "--right" if inline_value.is_none() => parsed.right = true,
+            "--shell" => {
+                let name = value()?;
+                parsed.shell = Shell::from_name(&name).ok_or_else(|| {
+                    format!("unknown shell \"{name}\" (expected bash, zsh, fish or plain)")
+                })?;
+            }
+            "--status" => parsed.shell_state.status = Some(parse_number(&flag, &value()?)?),
+            "--duration" => parsed.shell_state.duration_ms = Some(parse_number(&flag, &value()?)?),
+            "--jobs" => parsed.shell_state.jobs = Some(parse_number(&flag, &value()?)?),
+            "--width" => {
+                // Shells report 0 when the width is unknown
+                let width: usize = parse_number(&flag, &value()?)?;
+                parsed.shell_state.width = Some(width).filter(|w| *w > 0);
+            }
+            _ => return Err(format!("unknown argument \"{arg}\"")),
+        }
+    }
+
+    Ok(Command::Prompt(parsed))
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got \"{value}\""))
+}
@@ -0,0 +1,29 @@
+# gprompt integration for bash
+# Add `eval "$(gprompt init bash)"` to ~/.bashrc
+
+__gprompt_precmd() {
+    local status=$?
+    local jobs=0 job
+    for job in $(jobs -p); do
+        jobs=$((jobs + 1))
+    done
+
+    local duration=()
+    if ((__gprompt_start > 0)); then
+        local now=${EPOCHREALTIME//[!0-9]/}
+        duration=(--duration $(((now - __gprompt_start) / 1000)))
+        __gprompt_start=0
+    fi
+
+    PS1="$(::GPROMPT:: --shell bash --status "$status" "${duration[@]}" --jobs "$jobs" --width "${COLUMNS:-0}")"
+}
+
+# PS0 is expanded just before a command runs; bash 5 provides a microsecond clock
+__gprompt_start=0
+if [[ -n $EPOCHREALTIME && $PS0 != *__gprompt_start* ]]; then
+    PS0='${__gprompt_start:0:$((__gprompt_start=${EPOCHREALTIME//[!0-9]/}, 0))}'"$PS0"
+fi
+
+if [[ ";${PROMPT_COMMAND}" != *";__gprompt_precmd"* ]]; then
+    PROMPT_COMMAND="__gprompt_precmd${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
@@ -0,0 +1,7 @@
+# gprompt integration for fish
+# Add `gprompt init fish | source` to ~/.config/fish/config.fish
+
+function fish_prompt
+    set -l gprompt_status $status
+    ::GPROMPT:: --shell fish --status $gprompt_status --duration $CMD_DURATION --jobs (count (jobs -p)) --width $COLUMNS
+end
@@ -1,11 +1,21 @@
+mod cli;
 mod models;
 mod services;
-use ansi_term::Colour;
 use dirs::home_dir;
 
-use crate::services::git_status::get_git_repo_state;
+use crate::cli::{parse_args, Command, PromptArgs};
+use crate::models::git_repo_state::GitRepoState;
+use crate::models::prompt_config::PromptConfig;
+use crate::models::shell::Shell;
+use crate::models::status_report::StatusReport;
+use crate::models::template::Template;
+use crate::services::config_loader::load_config;
+use crate::services::git_status::{get_git_repo_state, ScanOptions};
+use crate::services::prompt_renderer::PromptContext;
+use crate::services::shell_init::init_script;
 
 use std::path::Path;
+use std::time::Duration;
 
 fn get_path(cwd: &Path) -> String {
     let home_path = match home_dir() {
@@ -25,6 +35,28 @@ fn get_path(cwd: &Path) -> String {
 }
 
 fn main() {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("gprompt: {err}");
+            std::process::exit(2);
+        }
+    };
+
+    let config = load_config().unwrap_or_else(|err| {
+        eprintln!("gprompt: {err}");
+        PromptConfig::default()
+    });
+
+    match command {
+        Command::Prompt(args) => print_prompt(&config, &args),
+        Command::Init(shell) => print_init(&config, shell),
+        Command::Status => print_status(&config),
+        Command::Daemon => run_daemon(&config),
+    }
+}
+
+fn print_prompt(config: &PromptConfig, args: &PromptArgs) {
     let path = match std::env::current_dir() {
         Ok(p) => p,
         Err(_) => {
@@ -33,38 +65,117 @@ fn main() {
         }
     };
 
+    // `--right` renders `right_format`, for shells with a right-hand prompt
+    let template = if args.right {
+        &config.right_format
+    } else {
+        &config.format
+    };
     let path_segment = get_path(&path);
-    let git_state = get_git_repo_state(&path);
-
-    println!();
-    print!("{} ", Colour::Blue.paint(path_segment));
-    if let Some(state) = git_state {
-        if let Some(special_display) = state.special_state.display_name() {
-            // Display special state instead of branch
-            print!("{} ", Colour::White.dimmed().paint(special_display));
-        } else if let Some(branch) = state.branch {
-            // Normal state: display branch
-            print!("{} ", Colour::White.dimmed().paint(branch));
-        }
-        if state.ahead > 0 {
-            print!("{}", Colour::Cyan.paint("↑"));
-        }
-        if state.behind > 0 {
-            print!("{}", Colour::Cyan.paint("↓"));
-        }
-        if state.unstaged > 0 {
-            print!("{}", Colour::Red.paint("×"));
-        }
-        if state.staged > 0 {
-            print!("{}", Colour::Cyan.paint("+"));
+    let git_state = repo_state(config, &path, Some(template));
+
+    let context = PromptContext {
+        config,
+        path: &path_segment,
+        git_state: git_state.as_ref(),
+        shell: args.shell,
+        shell_state: args.shell_state,
+    };
+    print!("{}", context.render(template));
+}
+
+fn print_status(config: &PromptConfig) {
+    let path = match std::env::current_dir() {
+        Ok(p) => p,
+        Err(_) => {
+            eprintln!("Failed to get current directory");
+            return;
         }
-        if state.stashed > 0 {
-            print!("{}", Colour::Yellow.paint("•"));
+    };
+
+    let state = repo_state(config, &path, None);
+    let report = StatusReport::new(&path.display().to_string(), state);
+    match serde_json::to_string(&report) {
+        Ok(json) => println!("{json}"),
+        Err(err) => {
+            eprintln!("gprompt: {err}");
+            std::process::exit(1);
         }
-        if state.untracked > 0 {
-            print!("{}", Colour::Yellow.paint("*"));
+    }
+}
+
+/// Asks the daemon for the repository state, computing it directly if no daemon answers
+///
+/// When computing it directly, only the parts `template` shows are looked up (all of
+/// them without a template). The daemon always has the full state.
+fn repo_state(
+    config: &PromptConfig,
+    path: &Path,
+    template: Option<&Template>,
+) -> Option<GitRepoState> {
+    #[cfg(unix)]
+    if config.daemon_timeout > 0 {
+        use crate::services::daemon::{query_daemon, socket_path};
+        let timeout = Duration::from_millis(config.daemon_timeout);
+        if let Some(answer) = socket_path().and_then(|socket| query_daemon(&socket, path, timeout))
+        {
+            return answer;
         }
     }
-    println!();
-    print!("{} ", Colour::Purple.paint("❯"));
+
+    let options = ScanOptions {
+        variables: template.map(Template::variables),
+        ..scan_options(config)
+    };
+    get_git_repo_state(path, &options)
+}
+
+/// Builds the scan options described by the configuration
+fn scan_options(config: &PromptConfig) -> ScanOptions {
+    // A timeout of 0 means no limit
+    let budget = |ms: u64| Some(ms).filter(|ms| *ms > 0).map(Duration::from_millis);
+    ScanOptions {
+        status_timeout: budget(config.status_timeout),
+        describe: config.detached.describe,
+        describe_timeout: budget(config.detached.timeout),
+        ignore_submodules: config.ignore_submodules,
+        variables: None,
+    }
+}
+
+fn print_init(config: &PromptConfig, shell: Shell) {
+    // Invoke this binary by its full path, so the prompt works even if it isn't on PATH
+    let executable = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.to_str().map(str::to_string))
+        .unwrap_or_else(|| "gprompt".to_string());
+    let right_prompt = !config.right_format.segments().is_empty();
+    if let Some(script) = init_script(shell, &executable, right_prompt) {
+        print!("{script}");
+    }
+}
+
+#[cfg(unix)]
+fn run_daemon(config: &PromptConfig) {
+    use crate::services::daemon;
+    let Some(socket) = daemon::socket_path() else {
+        eprintln!("gprompt: cannot determine the daemon socket path");
+        std::process::exit(1);
+    };
+    // The daemon has time to spare, so it always finishes its scans
+    let options = ScanOptions {
+        status_timeout: None,
+        describe_timeout: None,
+        ..scan_options(config)
+    };
+    if let Err(err) = daemon::run_daemon(&socket, options) {
+        eprintln!("gprompt: {err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn run_daemon(_config: &PromptConfig) {
+    eprintln!("gprompt: the daemon is only supported on Unix");
+    std::process::exit(1);
 }
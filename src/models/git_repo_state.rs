@@ -1,8 +1,93 @@
 // src/models/git_repo_state.rs
 
 use crate::models::git_special_state::GitSpecialState;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default)]
+/// Relationship between the current branch and its upstream
+///
+/// Serialized as `{"kind": "tracking", "details": "origin/main"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "details", rename_all = "snake_case")]
+pub enum UpstreamState {
+    /// The branch has no upstream configured (it has never been pushed with `-u`),
+    /// although the repository has a remote to push it to
+    Missing,
+
+    /// An upstream is configured but no longer exists, usually because the branch was
+    /// deleted on the remote. Contains the upstream's short name.
+    Gone(String),
+
+    /// The branch tracks an existing upstream. Contains its short name (`origin/main`).
+    Tracking(String),
+}
+
+/// How far HEAD has drifted from the repository's default branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultBranch {
+    /// Short name of the default branch (`origin/main`)
+    pub name: String,
+
+    /// Commits on HEAD that are not on the default branch
+    pub ahead: usize,
+
+    /// Commits on the default branch that are not on HEAD
+    pub behind: usize,
+}
+
+/// The linked worktree (`git worktree add`) the current directory is in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeInfo {
+    /// Name of the worktree (its directory under `.git/worktrees`)
+    pub name: String,
+
+    /// Path of the main worktree, relative to this one (`../gprompt`). None when the
+    /// main repository is bare.
+    pub main_path: Option<String>,
+
+    /// The worktree is locked (`git worktree lock`), so `git worktree prune` leaves it alone
+    pub locked: bool,
+}
+
+/// Changed files, by kind of change
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChangeCounts {
+    /// New files. Always zero for the working tree, where new files are `untracked`.
+    pub added: usize,
+    pub modified: usize,
+    pub deleted: usize,
+
+    /// Files moved or renamed (detected by content similarity, like `git status`).
+    /// Always zero for the working tree, where a moved file is a deletion and an
+    /// untracked file.
+    pub renamed: usize,
+
+    /// Files that changed type, e.g. from a regular file to a symlink
+    pub typechanged: usize,
+}
+
+/// Submodules of the repository that need attention
+///
+/// Each submodule is counted once, under the first of these that applies.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SubmoduleCounts {
+    /// Submodules that have not been checked out (`git submodule update --init`)
+    pub uninitialized: usize,
+
+    /// Submodules whose HEAD is not the commit recorded in the superproject
+    pub new_commits: usize,
+
+    /// Submodules with modified or untracked files
+    pub dirty: usize,
+}
+
+impl SubmoduleCounts {
+    /// Number of submodules that need attention
+    pub fn total(&self) -> usize {
+        self.uninitialized + self.new_commits + self.dirty
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GitRepoState {
     /// Branch name (remains populated even in special states; display logic determines what to show)
     pub branch: Option<String>,
@@ -10,10 +95,46 @@ pub struct GitRepoState {
     /// Special git state (rebase, merge, cherry-pick, detached, etc) if any
     pub special_state: GitSpecialState,
 
+    /// Upstream of the current branch (None when HEAD is not on a branch)
+    pub upstream: Option<UpstreamState>,
+
     pub ahead: usize,
     pub behind: usize,
+
+    /// Commits ahead of and behind the branch `git push` updates, when that isn't the
+    /// upstream (zero otherwise)
+    pub push_ahead: usize,
+    pub push_behind: usize,
+
+    /// Divergence from the remote's default branch (`origin/HEAD`), if it is known
+    pub default_branch: Option<DefaultBranch>,
+
+    /// The linked worktree HEAD belongs to (None in the main worktree)
+    pub worktree: Option<WorktreeInfo>,
+
+    /// Linked worktrees whose directory has gone, which `git worktree prune` would remove
+    pub prunable_worktrees: usize,
+
+    /// Name of the superproject's directory, when this repository is one of its submodules
+    pub superproject: Option<String>,
+
+    /// Submodules that are uninitialized, have new commits or are dirty
+    pub submodules: SubmoduleCounts,
+
     pub staged: usize,
     pub unstaged: usize,
+
+    /// `staged` and `unstaged` broken down by kind of change
+    pub staged_changes: ChangeCounts,
+    pub unstaged_changes: ChangeCounts,
+
     pub untracked: usize,
     pub stashed: usize,
+
+    /// Files with unresolved merge conflicts
+    pub conflicted: usize,
+
+    /// The working-tree scan ran out of time, so `staged`, `unstaged`, `untracked`,
+    /// `conflicted` and `submodules` are unknown (and left at zero)
+    pub status_timed_out: bool,
 }
@@ -8,11 +8,14 @@
 //! # Examples
 //!
 //! ```
-//! use gprompt::models::git_special_state::{GitSpecialState, OperationProgress};
+//! use gprompt::models::git_special_state::{GitSpecialState, OperationProgress, RebaseInfo};
 //!
 //! // Create a rebasing state with progress
 //! let progress = OperationProgress::new(3, 7).unwrap();
-//! let state = GitSpecialState::Rebasing(Some(progress));
+//! let state = GitSpecialState::Rebasing(RebaseInfo {
+//!     progress: Some(progress),
+//!     ..Default::default()
+//! });
 //! assert_eq!(state.display_name(), Some("Rebasing 3/7".to_string()));
 //!
 //! // Create a detached HEAD state
@@ -20,8 +23,10 @@
 //! assert_eq!(state.display_name(), Some("Detached @ a1b2c3d".to_string()));
 //! ```
 
+use serde::{Deserialize, Serialize};
+
 /// Represents progress through a multi-step git operation
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OperationProgress {
     /// Current step number (1-indexed for display)
     pub current: usize,
@@ -53,50 +58,101 @@ impl OperationProgress {
     }
 }
 
+/// Details of a rebase in progress
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RebaseInfo {
+    /// Branch being rebased (None when rebasing a detached HEAD)
+    pub head_name: Option<String>,
+
+    /// What the branch is being rebased onto: a branch name, or a short SHA if no
+    /// branch points at it
+    pub onto: Option<String>,
+
+    /// Step progress, if known
+    pub progress: Option<OperationProgress>,
+}
+
+/// Details of a bisect in progress
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BisectInfo {
+    /// Number of commits marked good so far
+    pub good: usize,
+
+    /// Number of commits marked bad so far
+    pub bad: usize,
+
+    /// Revisions that may still be the first bad commit (None until both a good and a
+    /// bad commit are known)
+    pub remaining: Option<usize>,
+
+    /// Estimated number of steps left, as `git bisect` reports it
+    pub steps: Option<usize>,
+
+    /// Short SHA of the commit currently checked out for testing
+    pub candidate: Option<String>,
+}
+
 /// Represents special git operations in progress
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+///
+/// Serialized as `{"kind": "rebasing", "details": {...}}`, with `details` omitted for
+/// variants that carry no data.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "details", rename_all = "snake_case")]
 pub enum GitSpecialState {
     /// Repository is in normal state (on a branch, not in any special operation)
     #[default]
     Normal,
 
     /// Repository is in rebase state
-    /// Contains optional progress information (`OperationProgress` with fields `current` and `total`)
-    Rebasing(Option<OperationProgress>),
+    /// Contains the branch being rebased, its new base and progress, as far as known
+    Rebasing(RebaseInfo),
 
     /// Repository is in cherry-pick state
     /// Contains optional progress information (`OperationProgress` with fields `current` and `total`)
     CherryPicking(Option<OperationProgress>),
 
     /// Repository is in detached HEAD state
-    /// Contains the short commit SHA (up to 7 characters, or full SHA if shorter)
+    /// Contains the tag or `git describe` name of the commit, or its short SHA
+    /// (abbreviated like `git rev-parse --short`)
     Detached(String),
 
     /// Repository is in merge state
-    Merging,
+    /// Contains the branch being merged in (or its short SHA), if known
+    Merging(Option<String>),
 
     /// Repository is in revert state
-    Reverting,
+    /// Contains optional progress information (`OperationProgress` with fields `current` and `total`)
+    Reverting(Option<OperationProgress>),
 
     /// Repository is in bisect state
-    Bisecting,
+    /// Contains the marked commits, what is left to test and the current candidate
+    Bisecting(BisectInfo),
 
-    /// Repository is applying patches
-    ApplyingPatches,
+    /// Repository is applying patches with `git am`
+    /// Contains optional progress information (`OperationProgress` with fields `current` and `total`)
+    ApplyingPatches(Option<OperationProgress>),
 }
 
 impl GitSpecialState {
     /// Returns the display string for this special state
     ///
     /// # Returns
-    /// * `Option<String>` - Display string (e.g., "Rebasing 3/7"), or None if Normal
+    /// * `Option<String>` - Display string (e.g., "Rebasing feature/x onto main 3/7"), or
+    ///   None if Normal
     ///
     /// # Examples
     /// ```
-    /// use gprompt::models::git_special_state::{GitSpecialState, OperationProgress};
+    /// use gprompt::models::git_special_state::{GitSpecialState, OperationProgress, RebaseInfo};
     ///
-    /// let state = GitSpecialState::Rebasing(Some(OperationProgress::new(3, 7).unwrap()));
-    /// assert_eq!(state.display_name(), Some("Rebasing 3/7".to_string()));
+    /// let state = GitSpecialState::Rebasing(RebaseInfo {
+    ///     head_name: Some("feature/x".to_string()),
+    ///     onto: Some("main".to_string()),
+    ///     progress: OperationProgress::new(3, 7),
+    /// });
+    /// assert_eq!(
+    ///     state.display_name(),
+    ///     Some("Rebasing feature/x onto main 3/7".to_string())
+    /// );
     ///
     /// let normal = GitSpecialState::Normal;
     /// assert_eq!(normal.display_name(), None);
@@ -104,17 +160,38 @@ impl GitSpecialState {
     pub fn display_name(&self) -> Option<String> {
         match self {
             GitSpecialState::Normal => None,
-            GitSpecialState::Rebasing(progress) => {
-                Some(Self::format_with_progress("Rebasing", progress))
+            GitSpecialState::Rebasing(info) => {
+                let mut name = "Rebasing".to_string();
+                if let Some(head_name) = &info.head_name {
+                    name.push_str(&format!(" {head_name}"));
+                }
+                if let Some(onto) = &info.onto {
+                    name.push_str(&format!(" onto {onto}"));
+                }
+                Some(Self::format_with_progress(&name, &info.progress))
             }
             GitSpecialState::CherryPicking(progress) => {
                 Some(Self::format_with_progress("Cherry-picking", progress))
             }
             GitSpecialState::Detached(sha) => Some(format!("Detached @ {sha}")),
-            GitSpecialState::Merging => Some("Merging".to_string()),
-            GitSpecialState::Reverting => Some("Reverting".to_string()),
-            GitSpecialState::Bisecting => Some("Bisecting".to_string()),
-            GitSpecialState::ApplyingPatches => Some("Applying patches".to_string()),
+            GitSpecialState::Merging(Some(name)) => Some(format!("Merging {name}")),
+            GitSpecialState::Merging(None) => Some("Merging".to_string()),
+            GitSpecialState::Reverting(progress) => {
+                Some(Self::format_with_progress("Reverting", progress))
+            }
+            GitSpecialState::Bisecting(info) => {
+                let mut name = "Bisecting".to_string();
+                if let Some(steps) = info.steps {
+                    name.push_str(&format!(" ~{steps} steps"));
+                }
+                if let Some(candidate) = &info.candidate {
+                    name.push_str(&format!(" @ {candidate}"));
+                }
+                Some(name)
+            }
+            GitSpecialState::ApplyingPatches(progress) => {
+                Some(Self::format_with_progress("Applying patches", progress))
+            }
         }
     }
 
@@ -1,2 +1,6 @@
 pub mod git_repo_state;
 pub mod git_special_state;
+pub mod prompt_config;
+pub mod shell;
+pub mod status_report;
+pub mod template;
@@ -0,0 +1,540 @@
+// src/models/prompt_config.rs
+//! Data structures for prompt configuration.
+//!
+//! This module defines the `PromptConfig` struct and the types it is built from. Every
+//! field has a default matching gprompt's built-in look, so an empty configuration file
+//! (or no file at all) renders exactly the same prompt as before configuration existed.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::models::prompt_config::{ColourSpec, PromptConfig};
+//!
+//! let config = PromptConfig::default();
+//! assert_eq!(config.git_status.ahead.symbol, "↑");
+//! assert_eq!(config.git_status.ahead.colour, ColourSpec::Named("cyan".to_string()));
+//! ```
+
+use crate::models::template::Template;
+use ansi_term::{Colour, Style};
+use serde::{Deserialize, Serialize};
+
+/// Layout used when the configuration does not set `format`
+pub const DEFAULT_FORMAT: &str = "\n$path ($git_branch )$git_status\n$character ";
+
+/// A colour as written in the configuration file
+///
+/// Accepts `default` (the terminal's own foreground colour), one of the eight basic
+/// colour names (`black`, `red`, `green`, `yellow`, `blue`, `purple` (or `magenta`),
+/// `cyan`, `white`), a 256-colour palette index (`"208"`), or a hex RGB triple
+/// (`"#ff8800"`).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ColourSpec {
+    /// The terminal's default foreground colour
+    #[default]
+    Default,
+
+    /// One of the eight basic terminal colours
+    Named(String),
+
+    /// An index into the 256-colour palette
+    Fixed(u8),
+
+    /// A 24-bit colour
+    Rgb(u8, u8, u8),
+}
+
+impl ColourSpec {
+    /// Converts this spec into an `ansi_term::Colour`
+    ///
+    /// # Returns
+    /// * `Option<Colour>` - The colour, or None for the terminal default
+    pub fn to_colour(&self) -> Option<Colour> {
+        let colour = match self {
+            ColourSpec::Default => return None,
+            ColourSpec::Named(name) => match name.as_str() {
+                "black" => Colour::Black,
+                "red" => Colour::Red,
+                "green" => Colour::Green,
+                "yellow" => Colour::Yellow,
+                "blue" => Colour::Blue,
+                "purple" | "magenta" => Colour::Purple,
+                "cyan" => Colour::Cyan,
+                _ => Colour::White,
+            },
+            ColourSpec::Fixed(n) => Colour::Fixed(*n),
+            ColourSpec::Rgb(r, g, b) => Colour::RGB(*r, *g, *b),
+        };
+        Some(colour)
+    }
+}
+
+impl TryFrom<String> for ColourSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let lower = value.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "" | "default" => return Ok(ColourSpec::Default),
+            "black" | "red" | "green" | "yellow" | "blue" | "purple" | "magenta" | "cyan"
+            | "white" => return Ok(ColourSpec::Named(lower)),
+            _ => {}
+        }
+        if let Some(hex) = lower.strip_prefix('#') {
+            // Checking the digits first keeps the slicing below on character boundaries
+            if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+                if let (Ok(r), Ok(g), Ok(b)) = (channel(0), channel(2), channel(4)) {
+                    return Ok(ColourSpec::Rgb(r, g, b));
+                }
+            }
+        } else if let Ok(n) = lower.parse::<u8>() {
+            return Ok(ColourSpec::Fixed(n));
+        }
+        Err(format!("invalid colour \"{value}\""))
+    }
+}
+
+impl From<ColourSpec> for String {
+    fn from(value: ColourSpec) -> Self {
+        match value {
+            ColourSpec::Default => "default".to_string(),
+            ColourSpec::Named(name) => name,
+            ColourSpec::Fixed(n) => n.to_string(),
+            ColourSpec::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+/// Text attributes applied on top of a colour, written as a space-separated list
+/// such as `"bold underline"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct StyleAttributes {
+    pub bold: bool,
+    pub dimmed: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+}
+
+impl StyleAttributes {
+    /// Applies these attributes to an existing `ansi_term::Style`
+    pub fn apply(&self, mut style: Style) -> Style {
+        style.is_bold = self.bold;
+        style.is_dimmed = self.dimmed;
+        style.is_italic = self.italic;
+        style.is_underline = self.underline;
+        style.is_blink = self.blink;
+        style.is_reverse = self.reverse;
+        style.is_hidden = self.hidden;
+        style.is_strikethrough = self.strikethrough;
+        style
+    }
+}
+
+impl TryFrom<String> for StyleAttributes {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut attributes = StyleAttributes::default();
+        for word in value.split_whitespace() {
+            match word.to_ascii_lowercase().as_str() {
+                "bold" => attributes.bold = true,
+                "dimmed" | "dim" => attributes.dimmed = true,
+                "italic" => attributes.italic = true,
+                "underline" => attributes.underline = true,
+                "blink" => attributes.blink = true,
+                "reverse" => attributes.reverse = true,
+                "hidden" => attributes.hidden = true,
+                "strikethrough" => attributes.strikethrough = true,
+                _ => return Err(format!("invalid style attribute \"{word}\"")),
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+impl From<StyleAttributes> for String {
+    fn from(value: StyleAttributes) -> Self {
+        let flags = [
+            (value.bold, "bold"),
+            (value.dimmed, "dimmed"),
+            (value.italic, "italic"),
+            (value.underline, "underline"),
+            (value.blink, "blink"),
+            (value.reverse, "reverse"),
+            (value.hidden, "hidden"),
+            (value.strikethrough, "strikethrough"),
+        ];
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Appearance of a single prompt element
+///
+/// For indicators the `symbol` is printed when the indicator is active; for text
+/// segments such as the path or branch, the symbol is unused and only the colour
+/// and style apply.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndicatorConfig {
+    /// Glyph printed for this indicator (an empty symbol hides the indicator)
+    pub symbol: String,
+
+    /// Foreground colour
+    pub colour: ColourSpec,
+
+    /// Additional text attributes (bold, dimmed, ...)
+    pub style: StyleAttributes,
+
+    /// Print the counter after the symbol (`↑3` instead of `↑`); only applies to
+    /// indicators backed by a counter
+    pub show_count: bool,
+}
+
+impl IndicatorConfig {
+    fn new(symbol: &str, colour: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            colour: ColourSpec::Named(colour.to_string()),
+            style: StyleAttributes::default(),
+            show_count: false,
+        }
+    }
+
+    /// Returns the `ansi_term::Style` described by this configuration
+    pub fn ansi_style(&self) -> Style {
+        build_style(&self.colour, &self.style)
+    }
+}
+
+/// Appearance of the current directory segment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathConfig {
+    /// Foreground colour
+    pub colour: ColourSpec,
+
+    /// Additional text attributes (bold, dimmed, ...)
+    pub style: StyleAttributes,
+
+    /// Shorten parent directories to their first letter (`~/s/gprompt`) when the path
+    /// would take up more than a third of the terminal width. Only applies when the
+    /// width is known (see `gprompt init`).
+    pub abbreviate: bool,
+}
+
+impl Default for PathConfig {
+    fn default() -> Self {
+        Self {
+            colour: ColourSpec::Named("blue".to_string()),
+            style: StyleAttributes::default(),
+            abbreviate: false,
+        }
+    }
+}
+
+impl PathConfig {
+    /// Returns the `ansi_term::Style` described by this configuration
+    pub fn ansi_style(&self) -> Style {
+        build_style(&self.colour, &self.style)
+    }
+}
+
+/// Appearance of the last command's duration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DurationConfig {
+    /// Glyph printed before the duration
+    pub symbol: String,
+
+    /// Foreground colour
+    pub colour: ColourSpec,
+
+    /// Additional text attributes (bold, dimmed, ...)
+    pub style: StyleAttributes,
+
+    /// Shortest duration, in milliseconds, worth showing
+    pub min_time: u64,
+}
+
+impl Default for DurationConfig {
+    fn default() -> Self {
+        Self {
+            symbol: String::new(),
+            colour: ColourSpec::Named("yellow".to_string()),
+            style: StyleAttributes::default(),
+            min_time: 2000,
+        }
+    }
+}
+
+impl DurationConfig {
+    /// Returns the `ansi_term::Style` described by this configuration
+    pub fn ansi_style(&self) -> Style {
+        build_style(&self.colour, &self.style)
+    }
+}
+
+fn build_style(colour: &ColourSpec, attributes: &StyleAttributes) -> Style {
+    let base = match colour.to_colour() {
+        Some(colour) => colour.normal(),
+        None => Style::new(),
+    };
+    attributes.apply(base)
+}
+
+/// Indicators shown after the branch name, one per counter in `GitRepoState`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitStatusConfig {
+    /// Text printed between indicators in `$git_status`
+    pub separator: String,
+
+    pub ahead: IndicatorConfig,
+    pub behind: IndicatorConfig,
+
+    /// Ahead of and behind the push branch, when that isn't the upstream
+    pub push_ahead: IndicatorConfig,
+    pub push_behind: IndicatorConfig,
+
+    /// Ahead of and behind the default branch, shown by `$git_default` (not part of
+    /// `$git_status`)
+    pub default_ahead: IndicatorConfig,
+    pub default_behind: IndicatorConfig,
+
+    pub unstaged: IndicatorConfig,
+    pub staged: IndicatorConfig,
+
+    /// Unstaged and staged changes by kind, hidden (no symbol) by default
+    pub unstaged_modified: IndicatorConfig,
+    pub unstaged_deleted: IndicatorConfig,
+    pub unstaged_typechanged: IndicatorConfig,
+    pub staged_added: IndicatorConfig,
+    pub staged_modified: IndicatorConfig,
+    pub staged_deleted: IndicatorConfig,
+    pub staged_renamed: IndicatorConfig,
+    pub staged_typechanged: IndicatorConfig,
+
+    pub stashed: IndicatorConfig,
+    pub untracked: IndicatorConfig,
+    pub conflicted: IndicatorConfig,
+
+    /// Shown instead of the working-tree counters when the scan runs out of time
+    pub unknown: IndicatorConfig,
+
+    /// Shown when the branch's upstream no longer exists
+    pub gone: IndicatorConfig,
+
+    /// Shown when the branch has no upstream although the repository has a remote
+    pub no_upstream: IndicatorConfig,
+
+    /// Shown when the current linked worktree is locked
+    pub locked: IndicatorConfig,
+
+    /// Shown when the repository has linked worktrees whose directory has gone
+    pub prunable: IndicatorConfig,
+
+    /// Shown when submodules are uninitialized, have new commits or are dirty
+    pub submodules: IndicatorConfig,
+}
+
+impl Default for GitStatusConfig {
+    fn default() -> Self {
+        let mut default_ahead = IndicatorConfig::new("▴", "purple");
+        default_ahead.show_count = true;
+        let mut default_behind = IndicatorConfig::new("▾", "purple");
+        default_behind.show_count = true;
+        Self {
+            separator: String::new(),
+            ahead: IndicatorConfig::new("↑", "cyan"),
+            behind: IndicatorConfig::new("↓", "cyan"),
+            push_ahead: IndicatorConfig::new("⇡", "cyan"),
+            push_behind: IndicatorConfig::new("⇣", "cyan"),
+            default_ahead,
+            default_behind,
+            unstaged: IndicatorConfig::new("×", "red"),
+            staged: IndicatorConfig::new("+", "cyan"),
+            unstaged_modified: IndicatorConfig::new("", "red"),
+            unstaged_deleted: IndicatorConfig::new("", "red"),
+            unstaged_typechanged: IndicatorConfig::new("", "red"),
+            staged_added: IndicatorConfig::new("", "cyan"),
+            staged_modified: IndicatorConfig::new("", "cyan"),
+            staged_deleted: IndicatorConfig::new("", "cyan"),
+            staged_renamed: IndicatorConfig::new("", "cyan"),
+            staged_typechanged: IndicatorConfig::new("", "cyan"),
+            stashed: IndicatorConfig::new("•", "yellow"),
+            untracked: IndicatorConfig::new("*", "yellow"),
+            conflicted: IndicatorConfig::new("=", "red"),
+            unknown: IndicatorConfig::new("?", "yellow"),
+            gone: IndicatorConfig::new("⊘", "red"),
+            no_upstream: IndicatorConfig::new("∅", "yellow"),
+            locked: IndicatorConfig::new("⊡", "yellow"),
+            prunable: IndicatorConfig::new("✂", "yellow"),
+            submodules: IndicatorConfig::new("◫", "yellow"),
+        }
+    }
+}
+
+/// Which tags `git describe` may use to name a detached HEAD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DescribeStrategy {
+    /// Don't describe; show the short SHA unless HEAD is exactly at a tag
+    Off,
+
+    /// Any tag, annotated or lightweight (`git describe --tags`)
+    #[default]
+    Tags,
+
+    /// Annotated tags only (plain `git describe`)
+    Annotated,
+
+    /// Any ref, including branches (`git describe --all`)
+    All,
+}
+
+/// Which changes in a submodule count towards the `submodules` indicator, as in git's
+/// `diff.ignoreSubmodules`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IgnoreSubmodules {
+    /// Follow `diff.ignoreSubmodules` from git's configuration, or each submodule's
+    /// `submodule.<name>.ignore` setting
+    #[default]
+    Git,
+
+    /// Count new commits, modified files and untracked files
+    None,
+
+    /// Count new commits and modified files
+    Untracked,
+
+    /// Count new commits only
+    Dirty,
+
+    /// Ignore submodules altogether, in the file counts too
+    All,
+}
+
+/// How a detached HEAD is named
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DetachedConfig {
+    /// Refs used to describe a commit that isn't exactly at a tag, as in
+    /// `v2.3.1-4-ga1b2c3d`
+    pub describe: DescribeStrategy,
+
+    /// Time budget for describing the commit, in milliseconds (0 for no limit). The
+    /// short SHA is shown if it runs out.
+    pub timeout: u64,
+}
+
+impl Default for DetachedConfig {
+    fn default() -> Self {
+        Self {
+            describe: DescribeStrategy::default(),
+            timeout: 50,
+        }
+    }
+}
+
+/// Complete prompt configuration, as loaded from `config.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptConfig {
+    /// Layout of the prompt (see `models::template` for the syntax)
+    pub format: Template,
+
+    /// Layout of the right-hand prompt, for shells that support one (empty by default)
+    pub right_format: Template,
+
+    /// Current working directory segment
+    pub path: PathConfig,
+
+    /// Branch name, or special state (rebase, merge, ...) when one is in progress
+    pub git_branch: IndicatorConfig,
+
+    /// Prompt character printed on the input line
+    pub character: IndicatorConfig,
+
+    /// Upstream name shown by `$git_upstream`, after `symbol` (as in `main…origin/main`)
+    pub git_upstream: IndicatorConfig,
+
+    /// Linked worktree name shown by `$git_worktree`, after `symbol`
+    pub git_worktree: IndicatorConfig,
+
+    /// Path of the main worktree shown by `$git_main_worktree`, after `symbol`
+    pub git_main_worktree: IndicatorConfig,
+
+    /// Superproject name shown by `$git_superproject` inside a submodule, after `symbol`
+    pub git_superproject: IndicatorConfig,
+
+    /// Per-indicator settings for repository status
+    pub git_status: GitStatusConfig,
+
+    /// Time budget for scanning the working tree, in milliseconds (0 for no limit).
+    /// Branch, special state, ahead/behind and stash counts are always shown.
+    pub status_timeout: u64,
+
+    /// How long to wait for `gprompt daemon` to answer, in milliseconds, before
+    /// computing the repository state directly (0 to never ask the daemon)
+    pub daemon_timeout: u64,
+
+    /// Naming of detached HEADs
+    pub detached: DetachedConfig,
+
+    /// Which submodule changes are counted
+    pub ignore_submodules: IgnoreSubmodules,
+
+    /// Exit status of the last command, shown when non-zero
+    pub status: IndicatorConfig,
+
+    /// Run time of the last command
+    pub duration: DurationConfig,
+
+    /// Number of background jobs, shown when there are any
+    pub jobs: IndicatorConfig,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        let mut git_branch = IndicatorConfig::new("", "white");
+        git_branch.style.dimmed = true;
+        let mut git_upstream = IndicatorConfig::new("…", "white");
+        git_upstream.style.dimmed = true;
+        let mut git_main_worktree = IndicatorConfig::new("⌂", "white");
+        git_main_worktree.style.dimmed = true;
+        let mut git_superproject = IndicatorConfig::new("⊂", "white");
+        git_superproject.style.dimmed = true;
+        Self {
+            format: Template::parse(DEFAULT_FORMAT).expect("default format is valid"),
+            right_format: Template::default(),
+            path: PathConfig::default(),
+            git_branch,
+            character: IndicatorConfig::new("❯", "purple"),
+            git_upstream,
+            git_worktree: IndicatorConfig::new("⎇ ", "green"),
+            git_main_worktree,
+            git_superproject,
+            git_status: GitStatusConfig::default(),
+            status_timeout: 500,
+            daemon_timeout: 100,
+            detached: DetachedConfig::default(),
+            ignore_submodules: IgnoreSubmodules::default(),
+            status: IndicatorConfig::new("✘", "red"),
+            duration: DurationConfig::default(),
+            jobs: IndicatorConfig::new("✦", "blue"),
+        }
+    }
+}
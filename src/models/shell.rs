@@ -0,0 +1,105 @@
+// src/models/shell.rs
+//! Shell-specific quoting of prompt output.
+//!
+//! Shells that lay out the prompt themselves (bash's `PS1`, zsh's `PROMPT`) need to be
+//! told which bytes do not take up space on screen, otherwise colour codes are counted
+//! as visible characters and line editing breaks. They also interpret some characters
+//! in the prompt string, so text taken from the repository (branch names, paths) has
+//! to be escaped before it is printed.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::models::shell::Shell;
+//!
+//! assert_eq!(Shell::Zsh.escape("100%"), "100%%");
+//! assert_eq!(Shell::Bash.wrap_non_printing("\x1b[34m"), "\\[\x1b[34m\\]");
+//! assert_eq!(Shell::Plain.escape("$HOME"), "$HOME");
+//! ```
+
+/// The shell the prompt is rendered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shell {
+    /// bash, via `PS1`
+    Bash,
+
+    /// zsh, via `PROMPT`
+    Zsh,
+
+    /// fish, via `fish_prompt` (prints output verbatim)
+    Fish,
+
+    /// No shell-specific handling; escape sequences are printed as-is
+    #[default]
+    Plain,
+}
+
+impl Shell {
+    /// Looks up a shell by its name (`bash`, `zsh`, `fish` or `plain`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "plain" => Some(Shell::Plain),
+            _ => None,
+        }
+    }
+
+    /// Wraps a non-printing sequence (such as a colour code) so the shell does not
+    /// count it towards the prompt's width
+    pub fn wrap_non_printing(&self, sequence: &str) -> String {
+        if sequence.is_empty() {
+            return String::new();
+        }
+        match self {
+            Shell::Bash => format!("\\[{sequence}\\]"),
+            Shell::Zsh => format!("%{{{sequence}%}}"),
+            Shell::Fish | Shell::Plain => sequence.to_string(),
+        }
+    }
+
+    /// Escapes literal text so the shell prints it unchanged
+    ///
+    /// bash decodes backslash escapes in `PS1` and then expands `$` and backquotes;
+    /// zsh expands `%` sequences. fish and plain output need no escaping.
+    pub fn escape(&self, text: &str) -> String {
+        match self {
+            Shell::Bash => {
+                let mut escaped = String::with_capacity(text.len());
+                for c in text.chars() {
+                    match c {
+                        // `\\\\` decodes to `\\`, which expansion turns into `\`
+                        '\\' => escaped.push_str("\\\\\\\\"),
+                        // `\\` decodes to `\`, which quotes the following character
+                        '$' | '`' => {
+                            escaped.push_str("\\\\");
+                            escaped.push(c);
+                        }
+                        _ => escaped.push(c),
+                    }
+                }
+                escaped
+            }
+            Shell::Zsh => text.replace('%', "%%"),
+            Shell::Fish | Shell::Plain => text.to_string(),
+        }
+    }
+}
+
+/// Information about the shell session, passed in by the shell integration
+/// (see `gprompt init`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShellState {
+    /// Exit status of the last command
+    pub status: Option<i32>,
+
+    /// Run time of the last command, in milliseconds
+    pub duration_ms: Option<u64>,
+
+    /// Number of background jobs
+    pub jobs: Option<usize>,
+
+    /// Terminal width, in columns
+    pub width: Option<usize>,
+}
@@ -0,0 +1,53 @@
+// src/models/status_report.rs
+//! The document printed by `gprompt status --json`.
+//!
+//! The layout is versioned so that tools reading it (status lines, editor plugins) can
+//! tell when it changes incompatibly. New fields may be added without a version bump;
+//! renaming or removing a field, or changing its meaning, bumps `version`.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::models::git_repo_state::GitRepoState;
+//! use gprompt::models::status_report::StatusReport;
+//!
+//! let state = GitRepoState {
+//!     branch: Some("main".to_string()),
+//!     ..Default::default()
+//! };
+//! let report = StatusReport::new("/home/me/src", Some(state));
+//! let json = serde_json::to_value(&report).unwrap();
+//! assert_eq!(json["version"], 1);
+//! assert_eq!(json["state"]["branch"], "main");
+//! assert_eq!(json["state"]["special_state"]["kind"], "normal");
+//! ```
+
+use crate::models::git_repo_state::GitRepoState;
+use serde::{Deserialize, Serialize};
+
+/// Version of the `StatusReport` layout
+pub const STATUS_REPORT_VERSION: u32 = 1;
+
+/// Repository state for a directory, as reported to other programs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusReport {
+    /// Layout version (`STATUS_REPORT_VERSION` when produced by this build)
+    pub version: u32,
+
+    /// Directory the state was computed for
+    pub path: String,
+
+    /// Repository state, or None outside a git repository
+    pub state: Option<GitRepoState>,
+}
+
+impl StatusReport {
+    /// Creates a report in the current layout version
+    pub fn new(path: &str, state: Option<GitRepoState>) -> Self {
+        Self {
+            version: STATUS_REPORT_VERSION,
+            path: path.to_string(),
+            state,
+        }
+    }
+}
@@ -0,0 +1,402 @@
+// src/models/template.rs
+//! Parsing and rendering of prompt format strings.
+//!
+//! A format string is plain text interspersed with variables and conditional groups:
+//!
+//! - `$name` is replaced by the value of the variable `name` (e.g. `$path`)
+//! - `( ... )` is a conditional group: it is rendered only if at least one variable
+//!   inside it has a non-empty value, so separators around a missing value collapse
+//! - `\` escapes the next character, so `\$`, `\(`, `\)` and `\\` print literally
+//!
+//! Format strings are parsed once into a `Template` and can then be rendered any number
+//! of times against different variable values.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::models::template::{Template, Variable};
+//!
+//! let template = Template::parse("$path( on $git_branch)").unwrap();
+//!
+//! let rendered = template.render(|var| match var {
+//!     Variable::Path => "~/src".to_string(),
+//!     _ => String::new(),
+//! });
+//! assert_eq!(rendered, "~/src");
+//!
+//! let rendered = template.render(|var| match var {
+//!     Variable::Path => "~/src".to_string(),
+//!     Variable::GitBranch => "main".to_string(),
+//!     _ => String::new(),
+//! });
+//! assert_eq!(rendered, "~/src on main");
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Variables that can be referenced from a format string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variable {
+    /// Current directory, with the home directory shortened to `~`
+    Path,
+
+    /// Branch name, or the special state (rebase, merge, ...) when one is in progress
+    GitBranch,
+
+    /// All git status indicators, in their default order
+    GitStatus,
+
+    /// Prompt character
+    Character,
+
+    /// Name of the branch's upstream, when it has one
+    GitUpstream,
+
+    /// Commits ahead of and behind the default branch, when there are any
+    GitDefault,
+
+    /// Name of the linked worktree, when in one
+    GitWorktree,
+
+    /// Path of the main worktree relative to the current one, when in a linked worktree
+    GitMainWorktree,
+
+    /// Name of the superproject, when in a submodule
+    GitSuperproject,
+
+    /// Individual git status indicators
+    GitAhead,
+    GitBehind,
+    GitPushAhead,
+    GitPushBehind,
+    GitDefaultAhead,
+    GitDefaultBehind,
+    GitUnstaged,
+    GitStaged,
+    GitUnstagedModified,
+    GitUnstagedDeleted,
+    GitUnstagedTypechanged,
+    GitStagedAdded,
+    GitStagedModified,
+    GitStagedDeleted,
+    GitStagedRenamed,
+    GitStagedTypechanged,
+    GitStashed,
+    GitUntracked,
+    GitConflicted,
+    GitUnknown,
+    GitGone,
+    GitNoUpstream,
+    GitLocked,
+    GitPrunable,
+    GitSubmodules,
+
+    /// Exit status of the last command, when non-zero
+    Status,
+
+    /// Run time of the last command, when longer than the configured minimum
+    Duration,
+
+    /// Number of background jobs, when there are any
+    Jobs,
+}
+
+/// Indicators shown by `$git_status`, in order
+const GIT_STATUS_PARTS: &[Variable] = &[
+    Variable::GitGone,
+    Variable::GitNoUpstream,
+    Variable::GitAhead,
+    Variable::GitBehind,
+    Variable::GitPushAhead,
+    Variable::GitPushBehind,
+    Variable::GitConflicted,
+    Variable::GitUnstaged,
+    Variable::GitUnstagedModified,
+    Variable::GitUnstagedDeleted,
+    Variable::GitUnstagedTypechanged,
+    Variable::GitStaged,
+    Variable::GitStagedAdded,
+    Variable::GitStagedModified,
+    Variable::GitStagedDeleted,
+    Variable::GitStagedRenamed,
+    Variable::GitStagedTypechanged,
+    Variable::GitStashed,
+    Variable::GitUntracked,
+    Variable::GitSubmodules,
+    Variable::GitLocked,
+    Variable::GitPrunable,
+    Variable::GitUnknown,
+];
+
+/// Indicators shown by `$git_default`, in order
+const GIT_DEFAULT_PARTS: &[Variable] = &[Variable::GitDefaultAhead, Variable::GitDefaultBehind];
+
+impl Variable {
+    /// Returns the variables a composite variable (`$git_status`, `$git_default`) is
+    /// made of, or an empty slice for any other variable
+    pub fn parts(self) -> &'static [Variable] {
+        match self {
+            Variable::GitStatus => GIT_STATUS_PARTS,
+            Variable::GitDefault => GIT_DEFAULT_PARTS,
+            _ => &[],
+        }
+    }
+
+    /// Looks up a variable by the name used in format strings (without the `$`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let variable = match name {
+            "path" => Variable::Path,
+            "git_branch" => Variable::GitBranch,
+            "git_status" => Variable::GitStatus,
+            "character" => Variable::Character,
+            "git_ahead" => Variable::GitAhead,
+            "git_behind" => Variable::GitBehind,
+            "git_push_ahead" => Variable::GitPushAhead,
+            "git_push_behind" => Variable::GitPushBehind,
+            "git_default" => Variable::GitDefault,
+            "git_default_ahead" => Variable::GitDefaultAhead,
+            "git_default_behind" => Variable::GitDefaultBehind,
+            "git_unstaged" => Variable::GitUnstaged,
+            "git_staged" => Variable::GitStaged,
+            "git_unstaged_modified" => Variable::GitUnstagedModified,
+            "git_unstaged_deleted" => Variable::GitUnstagedDeleted,
+            "git_unstaged_typechanged" => Variable::GitUnstagedTypechanged,
+            "git_staged_added" => Variable::GitStagedAdded,
+            "git_staged_modified" => Variable::GitStagedModified,
+            "git_staged_deleted" => Variable::GitStagedDeleted,
+            "git_staged_renamed" => Variable::GitStagedRenamed,
+            "git_staged_typechanged" => Variable::GitStagedTypechanged,
+            "git_stashed" => Variable::GitStashed,
+            "git_untracked" => Variable::GitUntracked,
+            "git_conflicted" => Variable::GitConflicted,
+            "git_unknown" => Variable::GitUnknown,
+            "git_upstream" => Variable::GitUpstream,
+            "git_gone" => Variable::GitGone,
+            "git_no_upstream" => Variable::GitNoUpstream,
+            "git_worktree" => Variable::GitWorktree,
+            "git_main_worktree" => Variable::GitMainWorktree,
+            "git_locked" => Variable::GitLocked,
+            "git_prunable" => Variable::GitPrunable,
+            "git_superproject" => Variable::GitSuperproject,
+            "git_submodules" => Variable::GitSubmodules,
+            "status" => Variable::Status,
+            "duration" => Variable::Duration,
+            "jobs" => Variable::Jobs,
+            _ => return None,
+        };
+        Some(variable)
+    }
+}
+
+/// A parsed piece of a format string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Literal text, printed as-is
+    Text(String),
+
+    /// A variable reference
+    Variable(Variable),
+
+    /// A conditional group, hidden unless one of its variables is non-empty
+    Group(Vec<Segment>),
+}
+
+/// Errors that can occur while parsing a format string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `$` was followed by a name that is not a known variable
+    UnknownVariable(String),
+
+    /// A `$` was not followed by a variable name
+    MissingVariableName,
+
+    /// A `(` was never closed
+    UnclosedGroup,
+
+    /// A `)` had no matching `(`
+    UnexpectedGroupEnd,
+
+    /// The string ended with a lone `\`
+    TrailingEscape,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnknownVariable(name) => write!(f, "unknown variable ${name}"),
+            TemplateError::MissingVariableName => write!(f, "expected a variable name after $"),
+            TemplateError::UnclosedGroup => write!(f, "unclosed ( in format string"),
+            TemplateError::UnexpectedGroupEnd => write!(f, "unmatched ) in format string"),
+            TemplateError::TrailingEscape => write!(f, "format string ends with \\"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A parsed format string
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parses a format string
+    ///
+    /// # Returns
+    /// * `Ok(Template)` - The parsed template
+    /// * `Err(TemplateError)` - If the string contains unknown variables or unbalanced groups
+    pub fn parse(format: &str) -> Result<Self, TemplateError> {
+        let mut chars = format.chars().peekable();
+        let segments = parse_segments(&mut chars, false)?;
+        Ok(Self {
+            source: format.to_string(),
+            segments,
+        })
+    }
+
+    /// Returns the format string this template was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the parsed segments
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns every variable the template refers to, including the parts of composite
+    /// variables like `$git_status`
+    pub fn variables(&self) -> HashSet<Variable> {
+        let mut variables = HashSet::new();
+        collect_variables(&self.segments, &mut variables);
+        variables
+    }
+
+    /// Renders the template, calling `value` for each variable reference
+    pub fn render<F>(&self, mut value: F) -> String
+    where
+        F: FnMut(Variable) -> String,
+    {
+        let mut output = String::new();
+        render_segments(&self.segments, &mut value, &mut output);
+        output
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = TemplateError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Template::parse(&value)
+    }
+}
+
+impl From<Template> for String {
+    fn from(value: Template) -> Self {
+        value.source
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn parse_segments(chars: &mut Chars<'_>, in_group: bool) -> Result<Vec<Segment>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) => text.push(escaped),
+                None => return Err(TemplateError::TrailingEscape),
+            },
+            '$' => {
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || next == '_' {
+                        name.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if name.is_empty() {
+                    return Err(TemplateError::MissingVariableName);
+                }
+                let variable =
+                    Variable::from_name(&name).ok_or(TemplateError::UnknownVariable(name))?;
+                flush_text(&mut text, &mut segments);
+                segments.push(Segment::Variable(variable));
+            }
+            '(' => {
+                flush_text(&mut text, &mut segments);
+                segments.push(Segment::Group(parse_segments(chars, true)?));
+            }
+            ')' => {
+                if !in_group {
+                    return Err(TemplateError::UnexpectedGroupEnd);
+                }
+                flush_text(&mut text, &mut segments);
+                return Ok(segments);
+            }
+            _ => text.push(c),
+        }
+    }
+
+    if in_group {
+        return Err(TemplateError::UnclosedGroup);
+    }
+    flush_text(&mut text, &mut segments);
+    Ok(segments)
+}
+
+fn flush_text(text: &mut String, segments: &mut Vec<Segment>) {
+    if !text.is_empty() {
+        segments.push(Segment::Text(std::mem::take(text)));
+    }
+}
+
+fn collect_variables(segments: &[Segment], variables: &mut HashSet<Variable>) {
+    for segment in segments {
+        match segment {
+            Segment::Text(_) => {}
+            Segment::Variable(variable) => {
+                variables.insert(*variable);
+                variables.extend(variable.parts());
+            }
+            Segment::Group(children) => collect_variables(children, variables),
+        }
+    }
+}
+
+/// Renders segments into `output`, returning whether any variable had a value
+fn render_segments<F>(segments: &[Segment], value: &mut F, output: &mut String) -> bool
+where
+    F: FnMut(Variable) -> String,
+{
+    let mut has_value = false;
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Variable(variable) => {
+                let rendered = value(*variable);
+                if !rendered.is_empty() {
+                    has_value = true;
+                    output.push_str(&rendered);
+                }
+            }
+            Segment::Group(children) => {
+                let mut group = String::new();
+                if render_segments(children, value, &mut group) {
+                    has_value = true;
+                    output.push_str(&group);
+                }
+            }
+        }
+    }
+    has_value
+}
@@ -0,0 +1,59 @@
+// src/services/background.rs
+//! Work that runs on a background thread, so the caller can stop waiting for it.
+//!
+//! Scanning a large repository can take longer than a prompt can afford. Work started
+//! here runs on its own thread; if it hasn't finished when the time budget runs out,
+//! the caller moves on and the result is dropped once the thread is done.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::services::background::{run_with_timeout, Background};
+//! use std::time::Duration;
+//!
+//! assert_eq!(run_with_timeout(Duration::from_secs(5), || 1 + 1), Some(2));
+//!
+//! let slow = Background::spawn(|| std::thread::sleep(Duration::from_secs(5)));
+//! assert!(slow.wait(Duration::from_millis(10)).is_none());
+//! ```
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Work running on a background thread
+pub struct Background<T> {
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T: Send + 'static> Background<T> {
+    /// Starts running `work` on a new thread
+    pub fn spawn(work: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            // The receiver is gone if the caller stopped waiting
+            let _ = sender.send(work());
+        });
+        Self { receiver }
+    }
+
+    /// Waits up to `timeout` for the work to finish
+    ///
+    /// # Returns
+    /// * `Some(T)` - The work's result
+    /// * `None` - If it didn't finish in time, or panicked
+    pub fn wait(self, timeout: Duration) -> Option<T> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+/// Runs `work` on a background thread and waits up to `timeout` for its result
+///
+/// # Returns
+/// * `Some(T)` - The work's result
+/// * `None` - If it didn't finish in time, or panicked
+pub fn run_with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Option<T> {
+    Background::spawn(work).wait(timeout)
+}
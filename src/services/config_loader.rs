@@ -0,0 +1,118 @@
+// src/services/config_loader.rs
+//! Locating and loading the prompt configuration file.
+//!
+//! The configuration file is looked up in this order:
+//!
+//! 1. The path in the `GPROMPT_CONFIG` environment variable
+//! 2. `$XDG_CONFIG_HOME/gprompt/config.toml`
+//! 3. `~/.config/gprompt/config.toml`
+//!
+//! A missing file is not an error: the built-in defaults are used instead. Values in
+//! the file are merged over the defaults, so a file only needs to mention the settings
+//! it changes.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::services::config_loader::parse_config;
+//!
+//! let config = parse_config("[git_status.untracked]\nsymbol = \"?\"\n").unwrap();
+//! assert_eq!(config.git_status.untracked.symbol, "?");
+//! // Unmentioned settings keep their defaults
+//! assert_eq!(config.git_status.staged.symbol, "+");
+//! ```
+
+use crate::models::prompt_config::PromptConfig;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the configuration file location
+pub const CONFIG_ENV_VAR: &str = "GPROMPT_CONFIG";
+
+/// Errors that can occur while loading the configuration file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file exists but could not be read
+    Io(PathBuf, std::io::Error),
+
+    /// The file is not valid TOML or does not match the expected schema
+    Parse(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "failed to read {}: {err}", path.display()),
+            ConfigError::Parse(msg) => write!(f, "invalid configuration: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Returns the path the configuration file is expected at
+///
+/// # Returns
+/// * `Some(PathBuf)` - The override or XDG location (the file may not exist)
+/// * `None` - If no location can be determined (no override and no home directory)
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV_VAR).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+    Some(config_home.join("gprompt").join("config.toml"))
+}
+
+/// Loads the configuration from its default location
+///
+/// # Returns
+/// * `Ok(PromptConfig)` - The parsed configuration, or defaults if no file exists
+/// * `Err(ConfigError)` - If the file exists but cannot be read or parsed
+pub fn load_config() -> Result<PromptConfig, ConfigError> {
+    match config_path() {
+        Some(path) => load_config_from(&path),
+        None => Ok(PromptConfig::default()),
+    }
+}
+
+/// Loads the configuration from an explicit path, falling back to defaults if the
+/// file does not exist
+pub fn load_config_from(path: &Path) -> Result<PromptConfig, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => parse_config(&contents),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(PromptConfig::default()),
+        Err(err) => Err(ConfigError::Io(path.to_path_buf(), err)),
+    }
+}
+
+/// Parses configuration file contents, merging them over the defaults
+pub fn parse_config(contents: &str) -> Result<PromptConfig, ConfigError> {
+    let overrides: toml::Value =
+        toml::from_str(contents).map_err(|err| ConfigError::Parse(err.to_string()))?;
+    let mut merged = toml::Value::try_from(PromptConfig::default())
+        .map_err(|err| ConfigError::Parse(err.to_string()))?;
+    merge(&mut merged, overrides);
+    merged
+        .try_into()
+        .map_err(|err: toml::de::Error| ConfigError::Parse(err.to_string()))
+}
+
+/// Recursively overlays `overrides` onto `base`, table by table
+fn merge(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
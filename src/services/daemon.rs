@@ -0,0 +1,475 @@
+// src/services/daemon.rs
+//! Background daemon that keeps repository state in memory.
+//!
+//! `gprompt daemon` listens on a Unix socket and answers queries for the state of the
+//! repository containing a directory. The first query for a repository computes its
+//! state and starts watching its working tree; after that the cached state is returned
+//! until a filesystem notification marks it stale, at which point it is refreshed in
+//! the background (or on the next query, whichever comes first).
+//!
+//! Directories git ignores are not watched, and changes to ignored files don't mark
+//! the state stale. Repositories that haven't been queried for a while are dropped. If
+//! a working tree can't be watched (typically because `fs.inotify.max_user_watches`
+//! is exhausted), queries for it go unanswered for a while so that the prompt computes
+//! the state itself straight away.
+//!
+//! The protocol is one line of JSON each way:
+//!
+//! ```text
+//! -> {"path":"/home/me/src/gprompt"}
+//! <- {"state":{"branch":"main",...}}
+//! ```
+//!
+//! `state` is null outside a git repository. The daemon closes the connection without
+//! answering when it can't watch the repository.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::services::daemon::query_daemon;
+//! use std::path::Path;
+//! use std::time::{Duration, Instant};
+//!
+//! // Without a daemon listening, the query fails and the caller computes the state itself
+//! let answer = query_daemon(
+//!     Path::new("/nonexistent/gprompt.sock"),
+//!     Path::new("."),
+//!     Duration::from_millis(50),
+//! );
+//! assert!(answer.is_none());
+//! ```
+
+use crate::models::git_repo_state::GitRepoState;
+use crate::services::git_status::{get_git_repo_state, ScanOptions};
+use git2::Repository;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Environment variable that overrides the socket location
+pub const SOCKET_ENV_VAR: &str = "GPROMPT_SOCKET";
+
+/// How long the working tree has to be quiet before a stale entry is refreshed
+const REFRESH_DELAY: Duration = Duration::from_millis(50);
+
+/// How long a repository is kept (and watched) after it was last queried
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How long to wait before trying to watch a working tree again after failing to
+const WATCH_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// How often idle repositories are looked for when nothing changes
+const EVICT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    state: Option<GitRepoState>,
+}
+
+/// Returns the path of the daemon's socket
+///
+/// The socket is looked up in `GPROMPT_SOCKET`, then `$XDG_RUNTIME_DIR/gprompt.sock`,
+/// then `~/.cache/gprompt/daemon.sock`.
+///
+/// # Returns
+/// * `Some(PathBuf)` - The socket location (nothing may be listening on it)
+/// * `None` - If no location can be determined
+pub fn socket_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(SOCKET_ENV_VAR).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    if let Some(dir) = dirs::runtime_dir() {
+        return Some(dir.join("gprompt.sock"));
+    }
+    dirs::cache_dir().map(|dir| dir.join("gprompt").join("daemon.sock"))
+}
+
+/// Asks a running daemon for the state of the repository containing `cwd`
+///
+/// # Arguments
+/// * `socket` - Path of the daemon's socket
+/// * `cwd` - Directory to report on
+/// * `timeout` - How long to wait for each read from and write to the socket
+///
+/// # Returns
+/// * `Some(Some(GitRepoState))` - The daemon's answer for a repository
+/// * `Some(None)` - The daemon answered that `cwd` is not in a repository
+/// * `None` - If no daemon answered in time
+pub fn query_daemon(socket: &Path, cwd: &Path, timeout: Duration) -> Option<Option<GitRepoState>> {
+    let mut stream = UnixStream::connect(socket).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+
+    let mut request = serde_json::to_string(&Request {
+        path: cwd.to_path_buf(),
+    })
+    .ok()?;
+    request.push('\n');
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+    let response: Response = serde_json::from_str(&line).ok()?;
+    Some(response.state)
+}
+
+/// Listens on `socket` and answers queries until the process is killed
+///
+/// A socket file left behind by a daemon that is no longer running is replaced.
+///
+/// # Arguments
+/// * `socket` - Path to listen on
+/// * `options` - Options for every scan the daemon runs
+///
+/// # Returns
+/// * `Err(io::Error)` - If the socket cannot be bound, or another daemon is already
+///   listening on it
+pub fn run_daemon(socket: &Path, options: ScanOptions) -> io::Result<()> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", socket.display()),
+            ));
+        }
+        std::fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+
+    let (stale_sender, stale_receiver) = mpsc::channel();
+    let cache = Arc::new(Cache {
+        entries: Mutex::new(HashMap::new()),
+        unwatchable: Mutex::new(HashMap::new()),
+        stale_sender: Mutex::new(stale_sender),
+        options,
+    });
+    let refresher = Arc::clone(&cache);
+    std::thread::spawn(move || refresher.refresh_loop(stale_receiver));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let cache = Arc::clone(&cache);
+        std::thread::spawn(move || {
+            // A client that gave up has nothing to hear about
+            let _ = cache.answer(stream);
+        });
+    }
+    Ok(())
+}
+
+/// Repository state for every working tree queried so far, keyed by working directory
+struct Cache {
+    entries: Mutex<HashMap<PathBuf, Arc<Entry>>>,
+
+    /// Working directories that could not be watched, and when that last failed
+    unwatchable: Mutex<HashMap<PathBuf, Instant>>,
+
+    /// Told about working directories whose entries have gone stale
+    stale_sender: Mutex<mpsc::Sender<PathBuf>>,
+
+    /// Options for every scan
+    options: ScanOptions,
+}
+
+struct Entry {
+    workdir: PathBuf,
+
+    /// Last computed state (None until the first computation)
+    state: Mutex<Option<Option<GitRepoState>>>,
+
+    /// Set by the watcher when something in the working tree or git directory changes
+    stale: Arc<AtomicBool>,
+
+    /// Directories created since the last scan, which still need watching
+    new_dirs: Arc<Mutex<Vec<PathBuf>>>,
+
+    /// When the entry was last queried
+    last_used: Mutex<Instant>,
+
+    /// Kept alive for as long as the entry is cached
+    watcher: Mutex<RecommendedWatcher>,
+}
+
+impl Cache {
+    /// Reads one request from `stream` and writes back the answer
+    fn answer(&self, stream: UnixStream) -> io::Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let request: Request = serde_json::from_str(&line)?;
+
+        // Dropping the connection unanswered sends the client off to scan by itself
+        let Some(state) = self.state(&request.path) else {
+            return Ok(());
+        };
+        let response = Response { state };
+        let mut answer = serde_json::to_string(&response)?;
+        answer.push('\n');
+        (&stream).write_all(answer.as_bytes())
+    }
+
+    /// Returns the state of the repository containing `path`, computing it if the
+    /// cached copy is missing or stale
+    ///
+    /// # Returns
+    /// * `Some(Option<GitRepoState>)` - The state, or None outside a repository
+    /// * `None` - If the repository can't be watched, so there is no cached state
+    fn state(&self, path: &Path) -> Option<Option<GitRepoState>> {
+        let Some(workdir) = Repository::discover(path)
+            .ok()
+            .and_then(|repo| repo.workdir().map(Path::to_path_buf))
+        else {
+            return Some(None);
+        };
+        let entry = self.entry(&workdir)?;
+        if let Ok(mut last_used) = entry.last_used.lock() {
+            *last_used = Instant::now();
+        }
+        self.refresh(&entry)
+    }
+
+    /// Looks up the entry for `workdir`, creating it (and its watcher) if needed
+    ///
+    /// # Returns
+    /// * `None` - If the working tree can't be watched, or couldn't be recently
+    fn entry(&self, workdir: &Path) -> Option<Arc<Entry>> {
+        let mut entries = self.entries.lock().ok()?;
+        if let Some(entry) = entries.get(workdir) {
+            return Some(Arc::clone(entry));
+        }
+        let mut unwatchable = self.unwatchable.lock().ok()?;
+        if unwatchable
+            .get(workdir)
+            .is_some_and(|failed| failed.elapsed() < WATCH_RETRY_DELAY)
+        {
+            return None;
+        }
+        let sender = self.stale_sender.lock().ok()?.clone();
+        let Some(entry) = Entry::watch(workdir, sender) else {
+            unwatchable.insert(workdir.to_path_buf(), Instant::now());
+            return None;
+        };
+        unwatchable.remove(workdir);
+        let entry = Arc::new(entry);
+        entries.insert(workdir.to_path_buf(), Arc::clone(&entry));
+        Some(entry)
+    }
+
+    /// Returns the entry's state, dropping the entry if its watcher has stopped
+    /// covering the whole working tree
+    fn refresh(&self, entry: &Entry) -> Option<Option<GitRepoState>> {
+        let state = entry.state(&self.options);
+        if state.is_none() {
+            if let Ok(mut entries) = self.entries.lock() {
+                entries.remove(&entry.workdir);
+            }
+            if let Ok(mut unwatchable) = self.unwatchable.lock() {
+                unwatchable.insert(entry.workdir.clone(), Instant::now());
+            }
+        }
+        state
+    }
+
+    /// Drops entries that haven't been queried for a while, and forgets old failures
+    /// to watch
+    fn evict_idle(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, entry| {
+                entry
+                    .last_used
+                    .lock()
+                    .is_ok_and(|last_used| last_used.elapsed() < IDLE_TIMEOUT)
+            });
+        }
+        if let Ok(mut unwatchable) = self.unwatchable.lock() {
+            unwatchable.retain(|_, failed| failed.elapsed() < WATCH_RETRY_DELAY);
+        }
+    }
+
+    /// Refreshes stale entries once their working tree has been quiet for a moment,
+    /// and evicts idle ones
+    fn refresh_loop(&self, receiver: mpsc::Receiver<PathBuf>) {
+        loop {
+            match receiver.recv_timeout(EVICT_INTERVAL) {
+                Ok(workdir) => {
+                    let mut stale = HashSet::from([workdir]);
+                    while let Ok(workdir) = receiver.recv_timeout(REFRESH_DELAY) {
+                        stale.insert(workdir);
+                    }
+                    for workdir in stale {
+                        let entry = match self.entries.lock() {
+                            Ok(entries) => entries.get(&workdir).cloned(),
+                            Err(_) => return,
+                        };
+                        if let Some(entry) = entry {
+                            self.refresh(&entry);
+                        }
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+            self.evict_idle();
+        }
+    }
+}
+
+impl Entry {
+    /// Starts watching the working tree at `workdir`, and its git directory if that
+    /// lives elsewhere (as it does for linked worktrees)
+    ///
+    /// # Returns
+    /// * `None` - If the directories cannot be watched
+    fn watch(workdir: &Path, stale_sender: mpsc::Sender<PathBuf>) -> Option<Self> {
+        let repo = Repository::open(workdir).ok()?;
+        let stale = Arc::new(AtomicBool::new(true));
+        let new_dirs = Arc::new(Mutex::new(Vec::new()));
+
+        let flag = Arc::clone(&stale);
+        let created = Arc::clone(&new_dirs);
+        let events_repo = Repository::open(workdir).ok()?;
+        let key = workdir.to_path_buf();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let relevant = match event {
+                Ok(event) => {
+                    // The git directory is watched recursively already
+                    if event.kind.is_create() {
+                        let dirs = event.paths.iter().filter(|path| {
+                            path.starts_with(&key)
+                                && !path.starts_with(events_repo.path())
+                                && path.is_dir()
+                                && !is_ignored(&events_repo, path)
+                        });
+                        if let Ok(mut created) = created.lock() {
+                            created.extend(dirs.cloned());
+                        }
+                    }
+                    // Events without paths ask for a rescan
+                    event.paths.is_empty()
+                        || event
+                            .paths
+                            .iter()
+                            .any(|path| !is_ignored(&events_repo, path))
+                }
+                // Errors mean events may have been lost
+                Err(_) => true,
+            };
+            if relevant && !flag.swap(true, Ordering::SeqCst) {
+                let _ = stale_sender.send(key.clone());
+            }
+        })
+        .ok()?;
+
+        watch_tree(&mut watcher, &repo, workdir).ok()?;
+        // Linked worktrees keep refs and objects in the main repository's git directory
+        let common_dir = std::fs::read_to_string(repo.path().join("commondir"))
+            .ok()
+            .map(|dir| repo.path().join(dir.trim()));
+        for dir in std::iter::once(repo.path().to_path_buf()).chain(common_dir) {
+            if !dir.starts_with(workdir) {
+                watcher.watch(&dir, RecursiveMode::Recursive).ok()?;
+            }
+        }
+
+        Some(Self {
+            workdir: workdir.to_path_buf(),
+            state: Mutex::new(None),
+            stale,
+            new_dirs,
+            last_used: Mutex::new(Instant::now()),
+            watcher: Mutex::new(watcher),
+        })
+    }
+
+    /// Returns the cached state, recomputing it first if it is stale
+    ///
+    /// # Returns
+    /// * `None` - If directories created since the last scan cannot be watched
+    fn state(&self, options: &ScanOptions) -> Option<Option<GitRepoState>> {
+        let mut state = self.state.lock().ok()?;
+        // Watch new directories before scanning, so nothing created in them is missed
+        let new_dirs = std::mem::take(&mut *self.new_dirs.lock().ok()?);
+        if !new_dirs.is_empty() {
+            let repo = Repository::open(&self.workdir).ok()?;
+            let mut watcher = self.watcher.lock().ok()?;
+            for dir in new_dirs {
+                watch_tree(&mut watcher, &repo, &dir).ok()?;
+            }
+        }
+        // Clear the flag before computing, so changes made during the scan are not lost
+        if self.stale.swap(false, Ordering::SeqCst) || state.is_none() {
+            *state = Some(get_git_repo_state(&self.workdir, options));
+        }
+        state.clone()
+    }
+}
+
+/// Watches `dir` and every directory below it that git doesn't ignore
+///
+/// The git directory is watched recursively. Directories that vanish before they can
+/// be watched are skipped.
+fn watch_tree(
+    watcher: &mut RecommendedWatcher,
+    repo: &Repository,
+    dir: &Path,
+) -> notify::Result<()> {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mode = if dir.starts_with(repo.path()) {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        if let Err(err) = watcher.watch(&dir, mode) {
+            if dir.exists() {
+                return Err(err);
+            }
+            continue;
+        }
+        if mode == RecursiveMode::Recursive {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) && !is_ignored(repo, &path) {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether git ignores `path`, which is never the case for paths in the git directory
+/// or outside the working tree
+fn is_ignored(repo: &Repository, path: &Path) -> bool {
+    if path.starts_with(repo.path()) {
+        return false;
+    }
+    match repo
+        .workdir()
+        .and_then(|workdir| path.strip_prefix(workdir).ok())
+    {
+        Some(relative) if !relative.as_os_str().is_empty() => {
+            repo.is_path_ignored(relative).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
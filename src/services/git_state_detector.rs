@@ -13,20 +13,28 @@
 //! ```no_run
 //! use git2::Repository;
 //! use gprompt::services::git_state_detector::detect_special_state;
+//! use gprompt::services::git_status::ScanOptions;
 //!
 //! let repo = Repository::open(".").unwrap();
-//! let state = detect_special_state(&repo);
+//! let state = detect_special_state(&repo, &ScanOptions::default());
 //! if let Some(name) = state.display_name() {
 //!     println!("Repository is in state: {}", name);
 //! }
 //! ```
 
-use crate::models::git_special_state::{GitSpecialState, OperationProgress};
+use crate::models::git_special_state::{
+    BisectInfo, GitSpecialState, OperationProgress, RebaseInfo,
+};
+use crate::models::prompt_config::DescribeStrategy;
+use crate::services::background::run_with_timeout;
+use crate::services::git_status::ScanOptions;
+use std::path::Path;
 
 /// Detects the special state of a git repository
 ///
 /// # Arguments
 /// * `repo` - Reference to an open git2::Repository
+/// * `options` - How a detached HEAD is described, and how long that may take
 ///
 /// # Returns
 /// * `GitSpecialState` - The detected special state (or Normal if none)
@@ -36,7 +44,7 @@ use crate::models::git_special_state::{GitSpecialState, OperationProgress};
 /// - Returns `GitSpecialState::Normal` if detection fails or repository is clean
 /// - Side Effects: None (read-only operation)
 /// - Performance: Completes in <10ms for typical repositories
-pub fn detect_special_state(repo: &git2::Repository) -> GitSpecialState {
+pub fn detect_special_state(repo: &git2::Repository, options: &ScanOptions) -> GitSpecialState {
     let state = repo.state();
 
     #[allow(unreachable_patterns)]
@@ -45,83 +53,309 @@ pub fn detect_special_state(repo: &git2::Repository) -> GitSpecialState {
         | git2::RepositoryState::RebaseInteractive
         | git2::RepositoryState::RebaseMerge => detect_rebase_state(repo),
         git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence => {
-            // NOTE: git2 API does not expose step-by-step progress for sequences
-            GitSpecialState::CherryPicking(None)
+            GitSpecialState::CherryPicking(detect_sequence_progress(repo))
         }
-        git2::RepositoryState::Merge => GitSpecialState::Merging,
+        git2::RepositoryState::Merge => GitSpecialState::Merging(detect_merge_source(repo)),
         git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence => {
-            GitSpecialState::Reverting
+            GitSpecialState::Reverting(detect_sequence_progress(repo))
         }
-        git2::RepositoryState::Bisect => GitSpecialState::Bisecting,
-        git2::RepositoryState::ApplyMailbox | git2::RepositoryState::ApplyMailboxOrRebase => {
-            GitSpecialState::ApplyingPatches
+        git2::RepositoryState::Bisect => detect_bisect_state(repo),
+        git2::RepositoryState::ApplyMailbox => detect_apply_state(repo),
+        git2::RepositoryState::ApplyMailboxOrRebase => {
+            // Neither `applying` nor `rebasing` is present in `rebase-apply`, but only a
+            // rebase records what it is replaying onto
+            if repo.path().join("rebase-apply").join("onto").exists() {
+                detect_rebase_state(repo)
+            } else {
+                detect_apply_state(repo)
+            }
         }
-        git2::RepositoryState::Clean => detect_detached_head(repo),
+        git2::RepositoryState::Clean => detect_detached_head(repo, options),
         _ => GitSpecialState::Normal,
     }
 }
 
-/// Detects rebase state and extracts progress information
+/// Detects rebase state and extracts the branch, its new base and progress
+///
+/// `git rebase` keeps its state in `rebase-merge` (or `rebase-apply` for the older
+/// apply backend) inside the git directory: `head-name` holds the ref being rebased,
+/// `onto` the commit it is being replayed onto, and `msgnum`/`end` (`next`/`last` for
+/// `rebase-apply`) the current and total step.
 ///
 /// # Arguments
 /// * `repo` - Reference to an open git2::Repository
 ///
 /// # Returns
-/// * `GitSpecialState::Rebasing` - With progress if available, None otherwise
+/// * `GitSpecialState::Rebasing` - With whatever details could be read
 fn detect_rebase_state(repo: &git2::Repository) -> GitSpecialState {
-    match repo.open_rebase(None) {
-        Ok(mut rebase) => {
-            // Extract progress information
-            let current_step = rebase.operation_current();
-            let total_steps = rebase.len();
-
-            // current_step is 0-based, we need 1-based for display
-            if let Some(current) = current_step {
-                // current is 0-based index, convert to 1-based step number
-                let current_1based = current + 1;
-
-                // Create OperationProgress with validation
-                if let Some(progress) = OperationProgress::new(current_1based, total_steps) {
-                    GitSpecialState::Rebasing(Some(progress))
-                } else {
-                    // Progress validation failed, return without progress
-                    GitSpecialState::Rebasing(None)
-                }
-            } else {
-                // No current step information available
-                GitSpecialState::Rebasing(None)
-            }
+    let (dir, progress_files) = if repo.path().join("rebase-merge").is_dir() {
+        (repo.path().join("rebase-merge"), ("msgnum", "end"))
+    } else {
+        (repo.path().join("rebase-apply"), ("next", "last"))
+    };
+
+    let head_name = read_state_file(&dir.join("head-name"))
+        .filter(|name| name != "detached HEAD")
+        .map(|name| match name.strip_prefix("refs/heads/") {
+            Some(branch) => branch.to_string(),
+            None => name,
+        });
+    let onto = read_state_file(&dir.join("onto"))
+        .and_then(|sha| git2::Oid::from_str(&sha).ok())
+        .map(|oid| commit_name(repo, oid));
+    let progress = read_progress(&dir.join(progress_files.0), &dir.join(progress_files.1));
+
+    GitSpecialState::Rebasing(RebaseInfo {
+        head_name,
+        onto,
+        progress,
+    })
+}
+
+/// Names the commit being merged in
+///
+/// `MERGE_MSG` starts with the name the merge was asked for (`Merge branch 'feature/x'`)
+/// unless a custom message was given; otherwise the first commit in `MERGE_HEAD` is
+/// named by a branch pointing at it, or its short SHA.
+///
+/// # Arguments
+/// * `repo` - Reference to an open git2::Repository
+///
+/// # Returns
+/// * `Some(String)` - Branch name or short SHA of the incoming commit
+/// * `None` - If `MERGE_HEAD` can't be read
+fn detect_merge_source(repo: &git2::Repository) -> Option<String> {
+    let message = std::fs::read_to_string(repo.path().join("MERGE_MSG")).unwrap_or_default();
+    let first_line = message.lines().next().unwrap_or_default();
+    let from_message = [
+        "Merge branch '",
+        "Merge remote-tracking branch '",
+        "Merge tag '",
+        "Merge commit '",
+    ]
+    .iter()
+    .find_map(|prefix| first_line.strip_prefix(prefix))
+    .and_then(|rest| rest.split_once('\''))
+    .map(|(name, _)| name.to_string());
+    if from_message.is_some() {
+        return from_message;
+    }
+
+    let merge_head = std::fs::read_to_string(repo.path().join("MERGE_HEAD")).ok()?;
+    let oid = git2::Oid::from_str(merge_head.lines().next()?.trim()).ok()?;
+    Some(commit_name(repo, oid))
+}
+
+/// Detects `git am` state and extracts progress information
+///
+/// `git am` numbers the patches in its mailbox and keeps the current and last patch
+/// number in `rebase-apply/next` and `rebase-apply/last`.
+///
+/// # Arguments
+/// * `repo` - Reference to an open git2::Repository
+///
+/// # Returns
+/// * `GitSpecialState::ApplyingPatches` - With progress if available, None otherwise
+fn detect_apply_state(repo: &git2::Repository) -> GitSpecialState {
+    let dir = repo.path().join("rebase-apply");
+    GitSpecialState::ApplyingPatches(read_progress(&dir.join("next"), &dir.join("last")))
+}
+
+/// Works out how far a multi-commit cherry-pick or revert has got
+///
+/// git keeps the commits still to be applied, starting with the current one, in
+/// `sequencer/todo`, and records HEAD as it was when the sequence started in
+/// `sequencer/head`. Finished steps are counted as the commits made since then, so the
+/// count is off if commits are made by hand during the sequence, or if steps are
+/// applied with `--no-commit`.
+///
+/// # Arguments
+/// * `repo` - Reference to an open git2::Repository
+///
+/// # Returns
+/// * `Some(OperationProgress)` - For a sequence of commits
+/// * `None` - For a single-commit cherry-pick or revert, or if the progress can't be
+///   read (for instance because HEAD was reset to before the sequence started)
+fn detect_sequence_progress(repo: &git2::Repository) -> Option<OperationProgress> {
+    let dir = repo.path().join("sequencer");
+    let remaining = count_todo_lines(&dir.join("todo"))?;
+    let start = git2::Oid::from_str(&read_state_file(&dir.join("head"))?).ok()?;
+    let head = repo.head().ok()?.target()?;
+    if head != start && !repo.graph_descendant_of(head, start).ok()? {
+        return None;
+    }
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.push(head).ok()?;
+    revwalk.hide(start).ok()?;
+    let done = revwalk.count();
+    OperationProgress::new(done + 1, done + remaining)
+}
+
+/// Counts the instructions in a sequencer todo file, skipping comments
+fn count_todo_lines(path: &Path) -> Option<usize> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    Some(
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .count(),
+    )
+}
+
+/// Detects bisect state and works out how much is left to test
+///
+/// The commits marked so far are listed in `BISECT_LOG` (as `# bad: [sha]` and
+/// `# good: [sha]` comments), and `refs/bisect/` holds the current bad commit and every
+/// good one. The terms can be renamed (`git bisect start --term-new=...`), in which
+/// case `BISECT_TERMS` holds the bad term and then the good term.
+///
+/// # Arguments
+/// * `repo` - Reference to an open git2::Repository
+///
+/// # Returns
+/// * `GitSpecialState::Bisecting` - With whatever details could be read
+fn detect_bisect_state(repo: &git2::Repository) -> GitSpecialState {
+    let terms = std::fs::read_to_string(repo.path().join("BISECT_TERMS")).unwrap_or_default();
+    let mut terms = terms.lines().map(str::trim);
+    let bad_term = terms.next().filter(|t| !t.is_empty()).unwrap_or("bad");
+    let good_term = terms.next().filter(|t| !t.is_empty()).unwrap_or("good");
+
+    let log = std::fs::read_to_string(repo.path().join("BISECT_LOG")).unwrap_or_default();
+    let count_marks = |term: &str| {
+        let prefix = format!("# {term}: ");
+        log.lines().filter(|line| line.starts_with(&prefix)).count()
+    };
+
+    let remaining = count_bisect_candidates(repo, bad_term, good_term);
+    GitSpecialState::Bisecting(BisectInfo {
+        good: count_marks(good_term),
+        bad: count_marks(bad_term),
+        remaining,
+        steps: remaining.map(estimate_bisect_steps),
+        candidate: repo
+            .head()
+            .ok()
+            .and_then(|head| head.target())
+            .map(|oid| short_sha(repo, oid)),
+    })
+}
+
+/// Counts the commits reachable from the bad commit but from none of the good ones
+fn count_bisect_candidates(
+    repo: &git2::Repository,
+    bad_term: &str,
+    good_term: &str,
+) -> Option<usize> {
+    let bad = repo
+        .find_reference(&format!("refs/bisect/{bad_term}"))
+        .ok()?
+        .target()?;
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.push(bad).ok()?;
+
+    let mut has_good = false;
+    for reference in repo
+        .references_glob(&format!("refs/bisect/{good_term}-*"))
+        .ok()?
+        .flatten()
+    {
+        if let Some(good) = reference.target() {
+            revwalk.hide(good).ok()?;
+            has_good = true;
         }
-        Err(_) => {
-            // Fallback to Rebasing without progress
-            GitSpecialState::Rebasing(None)
+    }
+    if !has_good {
+        return None;
+    }
+    Some(revwalk.count())
+}
+
+/// Estimates the bisect steps left among `candidates` revisions, the way `git bisect`
+/// does for its "roughly N steps" message
+fn estimate_bisect_steps(candidates: usize) -> usize {
+    if candidates < 3 {
+        return 0;
+    }
+    let n = candidates.ilog2() as usize;
+    let e = 1 << n;
+    let x = candidates - e;
+    if e < 3 * x {
+        n
+    } else {
+        n - 1
+    }
+}
+
+/// Names a commit by a branch pointing at it (local branches first), falling back to
+/// its short SHA
+fn commit_name(repo: &git2::Repository, oid: git2::Oid) -> String {
+    for branch_type in [git2::BranchType::Local, git2::BranchType::Remote] {
+        let Ok(branches) = repo.branches(Some(branch_type)) else {
+            continue;
+        };
+        let name = branches
+            .flatten()
+            .filter(|(branch, _)| branch.get().target() == Some(oid))
+            .find_map(|(branch, _)| branch.name().ok().flatten().map(str::to_string));
+        if let Some(name) = name {
+            return name;
         }
     }
+    short_sha(repo, oid)
 }
 
-/// Detects detached HEAD state and extracts short commit SHA
+/// Reads a step counter and a total from two state files
+fn read_progress(current: &Path, total: &Path) -> Option<OperationProgress> {
+    let current = read_state_file(current)?.parse().ok()?;
+    let total = read_state_file(total)?.parse().ok()?;
+    OperationProgress::new(current, total)
+}
+
+/// Reads a single-line state file written by git, without its trailing newline
+fn read_state_file(path: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let line = contents.trim();
+    if line.is_empty() {
+        None
+    } else {
+        Some(line.to_string())
+    }
+}
+
+/// Abbreviates a commit SHA the way `git rev-parse --short` does: to `core.abbrev`
+/// characters (7 by default), extended until it is unambiguous in the repository
+fn short_sha(repo: &git2::Repository, oid: git2::Oid) -> String {
+    repo.find_object(oid, None)
+        .and_then(|object| object.short_id())
+        .ok()
+        .and_then(|short_id| short_id.as_str().map(str::to_string))
+        .unwrap_or_else(|| {
+            let sha = oid.to_string();
+            sha[..7.min(sha.len())].to_string()
+        })
+}
+
+/// Detects detached HEAD state and names the commit
 ///
 /// # Arguments
 /// * `repo` - Reference to an open git2::Repository
+/// * `options` - How the commit is described, and how long that may take
 ///
 /// # Returns
-/// * `GitSpecialState::Detached` - With short SHA (7 chars) if available
+/// * `GitSpecialState::Detached` - With the tag at HEAD, a `describe` name, or the short
+///   SHA
 /// * `GitSpecialState::Normal` - If HEAD is not detached
-fn detect_detached_head(repo: &git2::Repository) -> GitSpecialState {
+fn detect_detached_head(repo: &git2::Repository, options: &ScanOptions) -> GitSpecialState {
     match repo.head_detached() {
         Ok(true) => {
-            // HEAD is detached, extract the short SHA
+            // HEAD is detached, name the commit it points at
             match repo.head() {
                 Ok(head) => {
                     if let Some(oid) = head.target() {
-                        // Get short SHA (7 characters)
-                        let sha = oid.to_string();
-                        let short_sha = if sha.len() >= 7 {
-                            sha[..7].to_string()
-                        } else {
-                            sha
-                        };
-                        GitSpecialState::Detached(short_sha)
+                        let name = describe_with_timeout(repo, oid, options)
+                            .unwrap_or_else(|| short_sha(repo, oid));
+                        GitSpecialState::Detached(name)
                     } else {
                         GitSpecialState::Detached("unknown".to_string())
                     }
@@ -139,3 +373,72 @@ fn detect_detached_head(repo: &git2::Repository) -> GitSpecialState {
         }
     }
 }
+
+/// Runs `describe_commit` within `options.describe_timeout`
+///
+/// Describing walks history, which can be slow in large repositories, so it runs on a
+/// background thread with its own handle on the repository. The caller stops waiting
+/// when the time budget runs out.
+fn describe_with_timeout(
+    repo: &git2::Repository,
+    oid: git2::Oid,
+    options: &ScanOptions,
+) -> Option<String> {
+    let strategy = options.describe;
+    let timeout = match options.describe_timeout {
+        Some(timeout) => timeout,
+        None => return describe_commit(repo, oid, strategy),
+    };
+
+    let git_dir = repo.path().to_path_buf();
+    run_with_timeout(timeout, move || {
+        let repo = git2::Repository::open(&git_dir).ok()?;
+        describe_commit(&repo, oid, strategy)
+    })
+    .flatten()
+}
+
+/// Names a commit by the tag pointing at it, or failing that by `git describe`
+///
+/// # Returns
+/// * `Some(String)` - A tag (`v2.3.1`) or describe name (`v2.3.1-4-ga1b2c3d`)
+/// * `None` - If no tag (or ref, for `DescribeStrategy::All`) can be found
+fn describe_commit(
+    repo: &git2::Repository,
+    oid: git2::Oid,
+    strategy: DescribeStrategy,
+) -> Option<String> {
+    let commit = repo.find_object(oid, Some(git2::ObjectType::Commit)).ok()?;
+    // The SHA in `v2.3.1-4-ga1b2c3d` follows core.abbrev like other short SHAs
+    let mut format = git2::DescribeFormatOptions::new();
+    if let Ok(abbrev) = repo
+        .config()
+        .and_then(|config| config.get_i32("core.abbrev"))
+    {
+        if let Ok(abbrev) = u32::try_from(abbrev) {
+            format.abbreviated_size(abbrev);
+        }
+    }
+    let describe = |options: &git2::DescribeOptions| {
+        commit
+            .describe(options)
+            .and_then(|description| description.format(Some(&format)))
+            .ok()
+    };
+
+    // Any tag at HEAD wins, whatever the strategy
+    let mut exact = git2::DescribeOptions::new();
+    exact.describe_tags().max_candidates_tags(0);
+    if let Some(tag) = describe(&exact) {
+        return Some(tag);
+    }
+
+    let mut options = git2::DescribeOptions::new();
+    match strategy {
+        DescribeStrategy::Off => return None,
+        DescribeStrategy::Tags => options.describe_tags(),
+        DescribeStrategy::Annotated => &mut options,
+        DescribeStrategy::All => options.describe_all(),
+    };
+    describe(&options)
+}
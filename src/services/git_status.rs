@@ -1,33 +1,143 @@
 // src/services/git_status.rs
 
-use crate::models::git_repo_state::GitRepoState;
+use crate::models::git_repo_state::{
+    ChangeCounts, DefaultBranch, GitRepoState, SubmoduleCounts, UpstreamState, WorktreeInfo,
+};
+use crate::models::prompt_config::{DescribeStrategy, IgnoreSubmodules};
+use crate::models::template::Variable;
+use crate::services::background::Background;
 use crate::services::git_state_detector;
-use git2::{Repository, Status, StatusOptions};
+use git2::{
+    FileMode, Repository, Status, StatusEntry, StatusOptions, SubmoduleIgnore, SubmoduleStatus,
+    Worktree, WorktreeLockStatus,
+};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
 
-pub fn get_git_repo_state(cwd: &std::path::Path) -> Option<GitRepoState> {
+/// Index mode of a submodule entry (a "gitlink" to a commit in another repository)
+const GITLINK_MODE: u32 = 0o160000;
+
+/// Options controlling how much work `get_git_repo_state` does
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Time budget for the working-tree scan; when it runs out the scan is abandoned
+    /// and `GitRepoState::status_timed_out` is set. None waits for the scan to finish.
+    pub status_timeout: Option<Duration>,
+
+    /// Refs `git describe` may use to name a detached HEAD
+    pub describe: DescribeStrategy,
+
+    /// Time budget for describing a detached HEAD; when it runs out the short SHA is
+    /// shown. None waits for `describe` to finish.
+    pub describe_timeout: Option<Duration>,
+
+    /// Which submodule changes are counted
+    pub ignore_submodules: IgnoreSubmodules,
+
+    /// Variables that will be shown (see `Template::variables`). Lookups only needed
+    /// for other variables, such as the default branch, are skipped. None computes
+    /// everything.
+    pub variables: Option<HashSet<Variable>>,
+}
+
+impl ScanOptions {
+    /// Whether any of `variables` will be shown
+    fn wants(&self, variables: &[Variable]) -> bool {
+        match &self.variables {
+            Some(shown) => variables.iter().any(|variable| shown.contains(variable)),
+            None => true,
+        }
+    }
+}
+
+/// Works out the state of the repository containing `cwd`
+///
+/// Repositories can opt out of some of the work in their git config, like
+/// `bash.showDirtyState` for git's own `git-prompt.sh`:
+///
+/// * `gprompt.disable=true` - Treat the directory as if it wasn't in a repository
+/// * `gprompt.showStatus=false` - Skip the working-tree scan (no file counts)
+/// * `gprompt.showUntracked=false` - Don't look for untracked files
+///
+/// # Returns
+/// * `Some(GitRepoState)` - The state of the repository
+/// * `None` - Outside a repository, in a bare repository, or if `gprompt.disable` is set
+pub fn get_git_repo_state(cwd: &Path, options: &ScanOptions) -> Option<GitRepoState> {
+    let started = Instant::now();
     let mut repo = Repository::discover(cwd).ok()?;
-    if repo.is_bare() {
+    if repo.is_bare() || config_flag(&repo, "gprompt.disable", false) {
         return None;
     }
 
+    // Start the expensive scan first so the cheap lookups below overlap with it
+    let scan = status_scan(&repo, options);
+    let pending_counts = match (scan, options.status_timeout) {
+        (Some(scan), Some(_)) => repo
+            .workdir()
+            .map(|workdir| spawn_status_counts(workdir, scan)),
+        _ => None,
+    };
+
     let branch = branch_name(&repo);
+    let upstream = get_upstream(&repo);
     let (ahead, behind) = get_ahead_behind(&repo);
-    let staged = get_staged(&repo);
-    let unstaged = get_unstaged(&repo);
-    let untracked = get_untracked(&repo);
+    // These walk history or open other repositories, so are skipped unless shown
+    let (push_ahead, push_behind) =
+        if options.wants(&[Variable::GitPushAhead, Variable::GitPushBehind]) {
+            get_push_ahead_behind(&repo)
+        } else {
+            (0, 0)
+        };
+    let default_branch = if options.wants(Variable::GitDefault.parts()) {
+        get_default_branch(&repo)
+    } else {
+        None
+    };
+    let superproject = if options.wants(&[Variable::GitSuperproject]) {
+        get_superproject(&repo)
+    } else {
+        None
+    };
+    let worktree = get_worktree(&repo);
+    let prunable_worktrees = count_prunable_worktrees(&repo);
     let stashed = get_stash(&mut repo);
 
-    let special_state = git_state_detector::detect_special_state(&repo);
+    let special_state = git_state_detector::detect_special_state(&repo, options);
+
+    let counts = match (pending_counts, options.status_timeout, scan) {
+        (Some(pending), Some(timeout), _) => {
+            let remaining = timeout.saturating_sub(started.elapsed());
+            pending.wait(remaining).flatten()
+        }
+        (_, _, Some(scan)) => Some(get_status_counts(&repo, scan)),
+        // Turned off with `gprompt.showStatus`
+        (_, _, None) => Some(StatusCounts::default()),
+    };
+    let status_timed_out = counts.is_none();
+    let counts = counts.unwrap_or_default();
 
     Some(GitRepoState {
         branch,
         special_state,
+        upstream,
         ahead,
         behind,
-        staged,
-        unstaged,
-        untracked,
+        push_ahead,
+        push_behind,
+        default_branch,
+        worktree,
+        prunable_worktrees,
+        superproject,
+        submodules: counts.submodules,
+        staged: counts.staged,
+        unstaged: counts.unstaged,
+        staged_changes: counts.staged_changes,
+        unstaged_changes: counts.unstaged_changes,
+        untracked: counts.untracked,
         stashed,
+        conflicted: counts.conflicted,
+        status_timed_out,
     })
 }
 
@@ -41,60 +151,312 @@ fn branch_name(repository: &Repository) -> Option<String> {
     head.shorthand().map(|s| s.to_string())
 }
 
-fn get_staged(repository: &Repository) -> usize {
-    let mut opts = StatusOptions::new();
-    opts.include_untracked(true);
-    let statuses = match repository.statuses(Some(&mut opts)) {
-        Ok(s) => s,
-        Err(_) => return 0,
+/// Reads a boolean from the repository's git config (which includes the global config)
+fn config_flag(repo: &Repository, key: &str, default: bool) -> bool {
+    repo.config()
+        .and_then(|config| config.get_bool(key))
+        .unwrap_or(default)
+}
+
+/// Which untracked files are counted, as in git's `status.showUntrackedFiles`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UntrackedFiles {
+    /// Don't look for untracked files
+    No,
+
+    /// Count an untracked directory once, without looking inside it
+    Normal,
+
+    /// Count every file in untracked directories
+    All,
+}
+
+/// What the working-tree scan looks at
+#[derive(Debug, Clone, Copy)]
+struct StatusScan {
+    /// Which untracked files are counted
+    untracked: UntrackedFiles,
+
+    /// Which submodule changes are counted
+    ignore_submodules: SubmoduleIgnore,
+
+    /// Count submodules that need attention
+    submodules: bool,
+}
+
+/// Works out what the working-tree scan should look at, from `options` and the
+/// repository's `gprompt.*` settings
+///
+/// # Returns
+/// * `Some(StatusScan)` - The settings for the scan
+/// * `None` - If the scan is turned off with `gprompt.showStatus=false`
+fn status_scan(repo: &Repository, options: &ScanOptions) -> Option<StatusScan> {
+    if !config_flag(repo, "gprompt.showStatus", true) {
+        return None;
+    }
+    let untracked = if config_flag(repo, "gprompt.showUntracked", true) {
+        untracked_files(repo)
+    } else {
+        UntrackedFiles::No
     };
-    statuses
-        .iter()
-        .filter(|entry| {
-            entry.status().intersects(
-                Status::INDEX_NEW
-                    | Status::INDEX_MODIFIED
-                    | Status::INDEX_DELETED
-                    | Status::INDEX_TYPECHANGE
-                    | Status::INDEX_RENAMED,
-            )
-        })
-        .count()
+    Some(StatusScan {
+        untracked,
+        ignore_submodules: submodule_ignore(repo, options.ignore_submodules),
+        submodules: options.wants(&[Variable::GitSubmodules]),
+    })
 }
 
-fn get_unstaged(repository: &Repository) -> usize {
-    let mut opts = StatusOptions::new();
-    opts.include_untracked(true);
-    let statuses = match repository.statuses(Some(&mut opts)) {
-        Ok(s) => s,
-        Err(_) => return 0,
+/// Reads `status.showUntrackedFiles` (`no`, `normal` or `all`), which like git also
+/// accepts a boolean: false is `no` and true is `normal`
+fn untracked_files(repo: &Repository) -> UntrackedFiles {
+    let config = match repo.config() {
+        Ok(config) => config,
+        Err(_) => return UntrackedFiles::Normal,
     };
-    statuses
-        .iter()
-        .filter(|entry| {
-            entry.status().intersects(
-                Status::WT_MODIFIED
-                    | Status::WT_DELETED
-                    | Status::WT_TYPECHANGE
-                    | Status::WT_RENAMED,
-            )
-        })
-        .count()
+    let key = "status.showUntrackedFiles";
+    match config.get_bool(key) {
+        Ok(false) => UntrackedFiles::No,
+        Ok(true) => UntrackedFiles::Normal,
+        Err(_) => match config.get_string(key).as_deref() {
+            Ok("all") => UntrackedFiles::All,
+            _ => UntrackedFiles::Normal,
+        },
+    }
+}
+
+/// Counters derived from a single status scan of the working tree
+#[derive(Debug, Default)]
+struct StatusCounts {
+    staged: usize,
+    unstaged: usize,
+    staged_changes: ChangeCounts,
+    unstaged_changes: ChangeCounts,
+    untracked: usize,
+    conflicted: usize,
+    submodules: SubmoduleCounts,
 }
 
-fn get_untracked(repository: &Repository) -> usize {
+/// Scans the working tree once and classifies every entry
+///
+/// An entry can count towards several counters: a file that is staged and then
+/// modified again is both staged and unstaged. Conflicted entries are only counted
+/// as conflicted. Submodule changes that `ignore_submodules` rules out are skipped.
+///
+/// Staged renames are detected, so a moved file is one renamed entry rather than a
+/// deletion and an addition.
+fn get_status_counts(repository: &Repository, scan: StatusScan) -> StatusCounts {
+    let ignore = scan.ignore_submodules;
+    let mut counts = StatusCounts::default();
+    if scan.submodules && ignore != SubmoduleIgnore::All {
+        counts.submodules.uninitialized = count_uninitialized_submodules(repository);
+    }
     let mut opts = StatusOptions::new();
-    opts.include_untracked(true);
+    // Like `git status`, an untracked directory is one entry unless asked for all files
+    opts.include_untracked(scan.untracked != UntrackedFiles::No);
+    opts.recurse_untracked_dirs(scan.untracked == UntrackedFiles::All);
+    opts.exclude_submodules(ignore == SubmoduleIgnore::All);
+    // Only staged renames are detected, like `git status`: a file moved without
+    // telling git stays a deletion and an untracked file
+    opts.renames_head_to_index(true);
     let statuses = match repository.statuses(Some(&mut opts)) {
         Ok(s) => s,
-        Err(_) => return 0,
+        Err(_) => return counts,
+    };
+    for entry in statuses.iter() {
+        let mut status = entry.status();
+        if status.contains(Status::WT_MODIFIED) {
+            match submodule_change(repository, &entry, ignore) {
+                Some(SubmoduleChange::NewCommits) if scan.submodules => {
+                    counts.submodules.new_commits += 1
+                }
+                Some(SubmoduleChange::Dirty) if scan.submodules => counts.submodules.dirty += 1,
+                Some(SubmoduleChange::Ignored) => status.remove(Status::WT_MODIFIED),
+                _ => {}
+            }
+        }
+        if status.is_conflicted() {
+            counts.conflicted += 1;
+            continue;
+        }
+        let index_flags = [
+            Status::INDEX_NEW,
+            Status::INDEX_MODIFIED,
+            Status::INDEX_DELETED,
+            Status::INDEX_RENAMED,
+            Status::INDEX_TYPECHANGE,
+        ];
+        if count_change(&mut counts.staged_changes, status, index_flags) {
+            counts.staged += 1;
+        }
+        // New files in the working tree are untracked rather than added
+        let worktree_flags = [
+            Status::empty(),
+            Status::WT_MODIFIED,
+            Status::WT_DELETED,
+            Status::WT_RENAMED,
+            Status::WT_TYPECHANGE,
+        ];
+        if count_change(&mut counts.unstaged_changes, status, worktree_flags) {
+            counts.unstaged += 1;
+        }
+        if status.intersects(Status::WT_NEW) {
+            counts.untracked += 1;
+        }
+    }
+    counts
+}
+
+/// Adds an entry to the counter for its kind of change, if it has one
+///
+/// # Arguments
+/// * `changes` - Counters for one side of the comparison (index or working tree)
+/// * `status` - Status of the entry
+/// * `flags` - The flags meaning added, modified, deleted, renamed and typechanged
+///
+/// # Returns
+/// * `true` - If the entry was counted
+/// * `false` - If it has no change on this side
+fn count_change(changes: &mut ChangeCounts, status: Status, flags: [Status; 5]) -> bool {
+    let [added, modified, deleted, renamed, typechanged] = flags;
+    // A renamed file that was also edited has the modified flag too, but `git status`
+    // shows it as a rename
+    let counter = if status.intersects(renamed) {
+        &mut changes.renamed
+    } else if status.intersects(added) {
+        &mut changes.added
+    } else if status.intersects(modified) {
+        &mut changes.modified
+    } else if status.intersects(deleted) {
+        &mut changes.deleted
+    } else if status.intersects(typechanged) {
+        &mut changes.typechanged
+    } else {
+        return false;
     };
-    statuses
+    *counter += 1;
+    true
+}
+
+/// Runs `get_status_counts` on a background thread, so the caller can stop waiting
+/// for it. The thread opens its own handle on the repository at `workdir`.
+fn spawn_status_counts(workdir: &Path, scan: StatusScan) -> Background<Option<StatusCounts>> {
+    let workdir = workdir.to_path_buf();
+    Background::spawn(move || {
+        Repository::open(&workdir)
+            .ok()
+            .map(|repo| get_status_counts(&repo, scan))
+    })
+}
+
+/// How a submodule's working tree differs from the commit the superproject records
+enum SubmoduleChange {
+    /// The submodule's HEAD is another commit
+    NewCommits,
+
+    /// The submodule has modified or untracked files
+    Dirty,
+
+    /// The change is ruled out by `ignore_submodules`
+    Ignored,
+}
+
+/// Classifies a modified status entry, if it is a submodule
+///
+/// The status scan has already looked inside the submodule: the entry's working-tree
+/// side is the submodule's HEAD, so a different commit means new commits and the same
+/// commit means modified or untracked files. Only `untracked` needs a second look, to
+/// tell untracked files from modified ones.
+fn submodule_change(
+    repo: &Repository,
+    entry: &StatusEntry,
+    ignore: SubmoduleIgnore,
+) -> Option<SubmoduleChange> {
+    let delta = entry.index_to_workdir()?;
+    if delta.new_file().mode() != FileMode::Commit {
+        return None;
+    }
+    if delta.old_file().id() != delta.new_file().id() {
+        return Some(SubmoduleChange::NewCommits);
+    }
+    let dirty = match ignore {
+        SubmoduleIgnore::Dirty | SubmoduleIgnore::All => false,
+        SubmoduleIgnore::Untracked => entry
+            .path()
+            .and_then(|path| repo.submodule_status(path, ignore).ok())
+            .is_some_and(|status| {
+                status.intersects(
+                    SubmoduleStatus::WD_INDEX_MODIFIED | SubmoduleStatus::WD_WD_MODIFIED,
+                )
+            }),
+        _ => true,
+    };
+    Some(if dirty {
+        SubmoduleChange::Dirty
+    } else {
+        SubmoduleChange::Ignored
+    })
+}
+
+/// Counts submodules recorded in the index that haven't been checked out. The status
+/// scan doesn't report them, as an empty directory isn't a change.
+fn count_uninitialized_submodules(repo: &Repository) -> usize {
+    let (Some(workdir), Ok(index)) = (repo.workdir(), repo.index()) else {
+        return 0;
+    };
+    index
         .iter()
-        .filter(|entry| entry.status().intersects(Status::WT_NEW))
+        .filter(|entry| entry.mode == GITLINK_MODE)
+        .filter_map(|entry| String::from_utf8(entry.path).ok())
+        .filter(|path| !workdir.join(path).join(".git").exists())
         .count()
 }
 
+/// Works out which submodule changes to count
+///
+/// `IgnoreSubmodules::Git` uses `diff.ignoreSubmodules` when it is set, and otherwise
+/// leaves it to each submodule's `submodule.<name>.ignore` setting.
+fn submodule_ignore(repo: &Repository, setting: IgnoreSubmodules) -> SubmoduleIgnore {
+    match setting {
+        IgnoreSubmodules::Git => {
+            let configured = repo
+                .config()
+                .ok()
+                .and_then(|config| config.get_string("diff.ignoreSubmodules").ok());
+            match configured.as_deref() {
+                Some("none") => SubmoduleIgnore::None,
+                Some("untracked") => SubmoduleIgnore::Untracked,
+                Some("dirty") => SubmoduleIgnore::Dirty,
+                Some("all") => SubmoduleIgnore::All,
+                _ => SubmoduleIgnore::Unspecified,
+            }
+        }
+        IgnoreSubmodules::None => SubmoduleIgnore::None,
+        IgnoreSubmodules::Untracked => SubmoduleIgnore::Untracked,
+        IgnoreSubmodules::Dirty => SubmoduleIgnore::Dirty,
+        IgnoreSubmodules::All => SubmoduleIgnore::All,
+    }
+}
+
+/// Finds the superproject of a submodule, the way `git rev-parse
+/// --show-superproject-working-tree` does: the repository containing the working
+/// tree's parent directory must record the working tree as a submodule (a gitlink)
+///
+/// # Returns
+/// * `Some(String)` - The name of the superproject's working tree directory
+/// * `None` - If the repository is not a submodule
+fn get_superproject(repo: &Repository) -> Option<String> {
+    let workdir = repo.workdir()?;
+    let parent = Repository::discover(workdir.parent()?).ok()?;
+    let parent_workdir = parent.workdir()?;
+    let path = workdir.strip_prefix(parent_workdir).ok()?;
+    let entry = parent.index().ok()?.get_path(path, 0)?;
+    if entry.mode != GITLINK_MODE {
+        return None;
+    }
+    let name = parent_workdir.file_name()?;
+    Some(name.to_string_lossy().into_owned())
+}
+
 fn get_stash(repo: &mut Repository) -> usize {
     let mut count = 0;
     let _ = repo.stash_foreach(|_, _, _| {
@@ -104,6 +466,39 @@ fn get_stash(repo: &mut Repository) -> usize {
     count
 }
 
+/// Works out whether the current branch has an upstream, and whether it still exists
+///
+/// # Returns
+/// * `Some(UpstreamState)` - The upstream state of the branch HEAD is on
+/// * `None` - If HEAD is not on a branch, or the branch has no upstream and there is no
+///   remote to give it one
+fn get_upstream(repo: &Repository) -> Option<UpstreamState> {
+    if repo.head_detached().unwrap_or(true) {
+        return None;
+    }
+    // HEAD may name a branch with no commits yet, so read the reference it points at
+    let head = repo.find_reference("HEAD").ok()?;
+    let branch_ref = head.symbolic_target()?;
+
+    // The configured upstream's name is known even if the ref itself is gone
+    let upstream_ref = match repo.branch_upstream_name(branch_ref) {
+        Ok(name) => name.as_str()?.to_string(),
+        // A repository without remotes has nowhere to push to
+        Err(_) if repo.remotes().ok()?.is_empty() => return None,
+        Err(_) => return Some(UpstreamState::Missing),
+    };
+    let short_name = upstream_ref
+        .strip_prefix("refs/remotes/")
+        .or_else(|| upstream_ref.strip_prefix("refs/heads/"))
+        .unwrap_or(&upstream_ref)
+        .to_string();
+    if repo.find_reference(&upstream_ref).is_ok() {
+        Some(UpstreamState::Tracking(short_name))
+    } else {
+        Some(UpstreamState::Gone(short_name))
+    }
+}
+
 fn get_ahead_behind(repo: &Repository) -> (usize, usize) {
     let head = match repo.head() {
         Ok(h) => h,
@@ -134,3 +529,195 @@ fn get_ahead_behind(repo: &Repository) -> (usize, usize) {
         .unwrap_or((0, 0));
     (ahead, behind)
 }
+
+/// Counts commits ahead of and behind the branch `git push` would update, when that is
+/// not the upstream (as in fork-based workflows that pull from `upstream/main` and push
+/// to `origin/<branch>`)
+///
+/// # Returns
+/// * `(ahead, behind)` - Zero when the push branch is the upstream or doesn't exist
+fn get_push_ahead_behind(repo: &Repository) -> (usize, usize) {
+    let head = match repo.head() {
+        Ok(h) if h.is_branch() => h,
+        _ => return (0, 0),
+    };
+    let (Some(branch_ref), Some(head_oid)) = (head.name(), head.target()) else {
+        return (0, 0);
+    };
+    let push_ref = match push_ref(repo, branch_ref) {
+        Some(name) => name,
+        None => return (0, 0),
+    };
+    let upstream_ref = repo
+        .branch_upstream_name(branch_ref)
+        .ok()
+        .and_then(|name| name.as_str().map(str::to_string));
+    if upstream_ref.as_deref() == Some(push_ref.as_str()) {
+        return (0, 0);
+    }
+    let push_oid = match repo.refname_to_id(&push_ref) {
+        Ok(oid) => oid,
+        Err(_) => return (0, 0),
+    };
+    repo.graph_ahead_behind(head_oid, push_oid)
+        .unwrap_or((0, 0))
+}
+
+/// Works out the remote-tracking ref `git push` would update for a branch (git's
+/// `@{push}`)
+///
+/// The remote is `branch.<name>.pushRemote`, then `remote.pushDefault`, then the
+/// branch's fetch remote. Which branch on that remote is pushed to follows
+/// `push.default`.
+///
+/// # Arguments
+/// * `repo` - Reference to an open git2::Repository
+/// * `branch_ref` - Full name of the local branch (`refs/heads/main`)
+///
+/// # Returns
+/// * `Some(String)` - Full name of the remote-tracking ref (`refs/remotes/origin/main`)
+/// * `None` - If the branch would not be pushed anywhere
+fn push_ref(repo: &Repository, branch_ref: &str) -> Option<String> {
+    let branch = branch_ref.strip_prefix("refs/heads/")?;
+    let config = repo.config().ok()?;
+    let setting = |key: &str| config.get_string(key).ok();
+
+    let fetch_remote = setting(&format!("branch.{branch}.remote"));
+    let push_remote = setting(&format!("branch.{branch}.pushRemote"))
+        .or_else(|| setting("remote.pushDefault"))
+        .or_else(|| fetch_remote.clone())?;
+    let triangular = fetch_remote.as_deref() != Some(push_remote.as_str());
+    let upstream = || {
+        let merge = setting(&format!("branch.{branch}.merge"))?;
+        let name = repo.branch_upstream_name(branch_ref).ok()?;
+        Some((merge, name.as_str()?.to_string()))
+    };
+
+    match setting("push.default").as_deref().unwrap_or("simple") {
+        "nothing" => None,
+        "current" | "matching" => tracking_ref(repo, &push_remote, branch_ref),
+        "upstream" | "tracking" if !triangular => upstream().map(|(_, name)| name),
+        "upstream" | "tracking" => None,
+        // `simple`: like `current` when pushing elsewhere, otherwise the upstream as
+        // long as it has the same name
+        _ if triangular => tracking_ref(repo, &push_remote, branch_ref),
+        _ => upstream()
+            .filter(|(merge, _)| merge == branch_ref)
+            .map(|(_, name)| name),
+    }
+}
+
+/// Maps a local ref to the remote-tracking ref it is fetched into, using the remote's
+/// fetch refspecs
+fn tracking_ref(repo: &Repository, remote: &str, local_ref: &str) -> Option<String> {
+    let remote = repo.find_remote(remote).ok()?;
+    let tracking = remote
+        .refspecs()
+        .filter(|refspec| refspec.direction() == git2::Direction::Fetch)
+        .find(|refspec| refspec.src_matches(local_ref))?
+        .transform(local_ref)
+        .ok()?;
+    tracking.as_str().map(str::to_string)
+}
+
+/// Counts commits ahead of and behind the remote's default branch (`origin/HEAD`)
+///
+/// The remote is the current branch's fetch remote, or `origin`. `<remote>/HEAD` is set
+/// by `git clone`, or by `git remote set-head`.
+///
+/// # Returns
+/// * `Some(DefaultBranch)` - The default branch's short name and the counts
+/// * `None` - If the remote has no `HEAD`, or HEAD has no commits yet
+fn get_default_branch(repo: &Repository) -> Option<DefaultBranch> {
+    let head = repo.head().ok()?;
+    let head_oid = head.target()?;
+    let remote = head
+        .shorthand()
+        .filter(|_| head.is_branch())
+        .and_then(|branch| {
+            repo.config()
+                .ok()?
+                .get_string(&format!("branch.{branch}.remote"))
+                .ok()
+        })
+        .filter(|remote| remote != ".")
+        .unwrap_or_else(|| "origin".to_string());
+
+    let remote_head = repo
+        .find_reference(&format!("refs/remotes/{remote}/HEAD"))
+        .ok()?
+        .resolve()
+        .ok()?;
+    let name = remote_head.shorthand()?.to_string();
+    let (ahead, behind) = repo
+        .graph_ahead_behind(head_oid, remote_head.target()?)
+        .ok()?;
+    Some(DefaultBranch {
+        name,
+        ahead,
+        behind,
+    })
+}
+
+/// Describes the linked worktree the repository was opened from
+///
+/// # Returns
+/// * `Some(WorktreeInfo)` - The worktree's name, the main worktree's path relative to
+///   it, and whether it is locked
+/// * `None` - If this is the main worktree
+fn get_worktree(repo: &Repository) -> Option<WorktreeInfo> {
+    if !repo.is_worktree() {
+        return None;
+    }
+    let worktree = Worktree::open_from_repository(repo).ok()?;
+    let name = worktree.name()?.to_string();
+    let locked = matches!(worktree.is_locked(), Ok(WorktreeLockStatus::Locked(_)));
+
+    // A linked worktree's git directory names the main one in its `commondir` file
+    let main_path = std::fs::read_to_string(repo.path().join("commondir"))
+        .ok()
+        .and_then(|dir| Repository::open(repo.path().join(dir.trim())).ok())
+        .and_then(|main| {
+            let main_workdir = main.workdir()?.canonicalize().ok()?;
+            let workdir = repo.workdir()?.canonicalize().ok()?;
+            Some(relative_path(&workdir, &main_workdir))
+        })
+        .map(|path| path.to_string_lossy().into_owned());
+
+    Some(WorktreeInfo {
+        name,
+        main_path,
+        locked,
+    })
+}
+
+/// Counts linked worktrees that `git worktree prune` would remove, because their
+/// directory has been deleted and they aren't locked
+fn count_prunable_worktrees(repo: &Repository) -> usize {
+    let names = match repo.worktrees() {
+        Ok(names) => names,
+        Err(_) => return 0,
+    };
+    names
+        .iter()
+        .flatten()
+        .filter_map(|name| repo.find_worktree(name).ok())
+        .filter(|worktree| worktree.is_prunable(None).unwrap_or(false))
+        .count()
+}
+
+/// Expresses `to` relative to `from`, as in `../gprompt`. Both paths must be absolute.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let path: PathBuf = std::iter::repeat(Component::ParentDir)
+        .take(from.len() - common)
+        .chain(to[common..].iter().copied())
+        .collect();
+    if path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        path
+    }
+}
@@ -1,2 +1,8 @@
+pub mod background;
+pub mod config_loader;
+#[cfg(unix)]
+pub mod daemon;
 pub mod git_state_detector;
 pub mod git_status;
+pub mod prompt_renderer;
+pub mod shell_init;
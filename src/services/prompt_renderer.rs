@@ -0,0 +1,312 @@
+// src/services/prompt_renderer.rs
+//! Rendering of the prompt from a template, the configuration and repository state.
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::models::git_repo_state::GitRepoState;
+//! use gprompt::models::prompt_config::PromptConfig;
+//! use gprompt::models::shell::{Shell, ShellState};
+//! use gprompt::models::template::Template;
+//! use gprompt::services::prompt_renderer::PromptContext;
+//!
+//! let mut config = PromptConfig::default();
+//! config.git_branch.colour = Default::default();
+//! config.git_branch.style = Default::default();
+//! let state = GitRepoState {
+//!     branch: Some("main".to_string()),
+//!     ..Default::default()
+//! };
+//! let context = PromptContext {
+//!     config: &config,
+//!     path: "~/src",
+//!     git_state: Some(&state),
+//!     shell: Shell::Plain,
+//!     shell_state: ShellState::default(),
+//! };
+//! let template = Template::parse("[$git_branch]").unwrap();
+//! assert_eq!(context.render(&template), "[main]");
+//! ```
+
+use crate::models::git_repo_state::{GitRepoState, UpstreamState};
+use crate::models::prompt_config::{IndicatorConfig, PromptConfig};
+use crate::models::shell::{Shell, ShellState};
+use crate::models::template::{Template, Variable};
+use ansi_term::Style;
+
+/// Everything needed to render a prompt
+pub struct PromptContext<'a> {
+    /// Prompt configuration (styles and symbols)
+    pub config: &'a PromptConfig,
+
+    /// Display form of the current directory
+    pub path: &'a str,
+
+    /// Repository state, or None outside a git repository
+    pub git_state: Option<&'a GitRepoState>,
+
+    /// Shell the output is escaped for
+    pub shell: Shell,
+
+    /// Session details passed in by the shell integration
+    pub shell_state: ShellState,
+}
+
+impl PromptContext<'_> {
+    /// Renders `template` against this context
+    ///
+    /// Variable values are escaped for the target shell; literal text from the template
+    /// is printed as written, so it may contain the shell's own prompt escapes.
+    pub fn render(&self, template: &Template) -> String {
+        template.render(|variable| self.variable(variable))
+    }
+
+    /// Returns the styled value of a single variable, or an empty string if it has none
+    fn variable(&self, variable: Variable) -> String {
+        let config = self.config;
+        match variable {
+            Variable::Path => {
+                let path = match self.shell_state.width {
+                    Some(width)
+                        if config.path.abbreviate && self.path.chars().count() > width / 3 =>
+                    {
+                        abbreviate_path(self.path)
+                    }
+                    _ => self.path.to_string(),
+                };
+                self.paint(config.path.ansi_style(), &path)
+            }
+            Variable::Character => self.paint_element(&config.character, &config.character.symbol),
+            Variable::GitBranch => match self.git_state {
+                Some(state) => match state.special_state.display_name() {
+                    // Display special state instead of branch
+                    Some(special_display) => {
+                        self.paint_element(&config.git_branch, &special_display)
+                    }
+                    // Normal state: display branch
+                    None => state
+                        .branch
+                        .as_deref()
+                        .map(|branch| self.paint_element(&config.git_branch, branch))
+                        .unwrap_or_default(),
+                },
+                None => String::new(),
+            },
+            Variable::GitUpstream => match self.git_state.and_then(|s| s.upstream.as_ref()) {
+                Some(UpstreamState::Tracking(name)) => {
+                    let text = format!("{}{name}", config.git_upstream.symbol);
+                    self.paint_element(&config.git_upstream, &text)
+                }
+                _ => String::new(),
+            },
+            Variable::GitWorktree => match self.git_state.and_then(|s| s.worktree.as_ref()) {
+                Some(worktree) => {
+                    let text = format!("{}{}", config.git_worktree.symbol, worktree.name);
+                    self.paint_element(&config.git_worktree, &text)
+                }
+                None => String::new(),
+            },
+            Variable::GitMainWorktree => match self
+                .git_state
+                .and_then(|s| s.worktree.as_ref())
+                .and_then(|worktree| worktree.main_path.as_ref())
+            {
+                Some(path) => {
+                    let text = format!("{}{path}", config.git_main_worktree.symbol);
+                    self.paint_element(&config.git_main_worktree, &text)
+                }
+                None => String::new(),
+            },
+            Variable::GitSuperproject => {
+                match self.git_state.and_then(|s| s.superproject.as_deref()) {
+                    Some(name) => {
+                        let text = format!("{}{name}", config.git_superproject.symbol);
+                        self.paint_element(&config.git_superproject, &text)
+                    }
+                    None => String::new(),
+                }
+            }
+            Variable::GitDefault => self.join_indicators(Variable::GitDefault.parts()),
+            Variable::GitStatus => self.join_indicators(Variable::GitStatus.parts()),
+            Variable::GitAhead => self.indicator(|s| s.ahead, &config.git_status.ahead),
+            Variable::GitBehind => self.indicator(|s| s.behind, &config.git_status.behind),
+            Variable::GitPushAhead => {
+                self.indicator(|s| s.push_ahead, &config.git_status.push_ahead)
+            }
+            Variable::GitPushBehind => {
+                self.indicator(|s| s.push_behind, &config.git_status.push_behind)
+            }
+            Variable::GitDefaultAhead => self.indicator(
+                |s| s.default_branch.as_ref().map_or(0, |d| d.ahead),
+                &config.git_status.default_ahead,
+            ),
+            Variable::GitDefaultBehind => self.indicator(
+                |s| s.default_branch.as_ref().map_or(0, |d| d.behind),
+                &config.git_status.default_behind,
+            ),
+            Variable::GitUnstaged => self.indicator(|s| s.unstaged, &config.git_status.unstaged),
+            Variable::GitStaged => self.indicator(|s| s.staged, &config.git_status.staged),
+            Variable::GitUnstagedModified => self.indicator(
+                |s| s.unstaged_changes.modified,
+                &config.git_status.unstaged_modified,
+            ),
+            Variable::GitUnstagedDeleted => self.indicator(
+                |s| s.unstaged_changes.deleted,
+                &config.git_status.unstaged_deleted,
+            ),
+            Variable::GitUnstagedTypechanged => self.indicator(
+                |s| s.unstaged_changes.typechanged,
+                &config.git_status.unstaged_typechanged,
+            ),
+            Variable::GitStagedAdded => {
+                self.indicator(|s| s.staged_changes.added, &config.git_status.staged_added)
+            }
+            Variable::GitStagedModified => self.indicator(
+                |s| s.staged_changes.modified,
+                &config.git_status.staged_modified,
+            ),
+            Variable::GitStagedDeleted => self.indicator(
+                |s| s.staged_changes.deleted,
+                &config.git_status.staged_deleted,
+            ),
+            Variable::GitStagedRenamed => self.indicator(
+                |s| s.staged_changes.renamed,
+                &config.git_status.staged_renamed,
+            ),
+            Variable::GitStagedTypechanged => self.indicator(
+                |s| s.staged_changes.typechanged,
+                &config.git_status.staged_typechanged,
+            ),
+            Variable::GitStashed => self.indicator(|s| s.stashed, &config.git_status.stashed),
+            Variable::GitUntracked => self.indicator(|s| s.untracked, &config.git_status.untracked),
+            Variable::GitConflicted => {
+                self.indicator(|s| s.conflicted, &config.git_status.conflicted)
+            }
+            Variable::GitUnknown => self.indicator(
+                |s| usize::from(s.status_timed_out),
+                &config.git_status.unknown,
+            ),
+            Variable::GitGone => self.indicator(
+                |s| usize::from(matches!(s.upstream, Some(UpstreamState::Gone(_)))),
+                &config.git_status.gone,
+            ),
+            Variable::GitNoUpstream => self.indicator(
+                |s| usize::from(s.upstream == Some(UpstreamState::Missing)),
+                &config.git_status.no_upstream,
+            ),
+            Variable::GitLocked => self.indicator(
+                |s| usize::from(s.worktree.as_ref().is_some_and(|w| w.locked)),
+                &config.git_status.locked,
+            ),
+            Variable::GitPrunable => {
+                self.indicator(|s| s.prunable_worktrees, &config.git_status.prunable)
+            }
+            Variable::GitSubmodules => {
+                self.indicator(|s| s.submodules.total(), &config.git_status.submodules)
+            }
+            Variable::Status => match self.shell_state.status {
+                Some(status) if status != 0 => {
+                    let text = format!("{}{status}", config.status.symbol);
+                    self.paint_element(&config.status, &text)
+                }
+                _ => String::new(),
+            },
+            Variable::Duration => match self.shell_state.duration_ms {
+                Some(ms) if ms >= config.duration.min_time => {
+                    let text = format!("{}{}", config.duration.symbol, format_duration(ms));
+                    self.paint(config.duration.ansi_style(), &text)
+                }
+                _ => String::new(),
+            },
+            Variable::Jobs => match self.shell_state.jobs {
+                Some(jobs) if jobs > 0 => {
+                    let text = format!("{}{jobs}", config.jobs.symbol);
+                    self.paint_element(&config.jobs, &text)
+                }
+                _ => String::new(),
+            },
+        }
+    }
+
+    /// Renders `indicators` in order, joined by `git_status.separator`
+    fn join_indicators(&self, indicators: &[Variable]) -> String {
+        indicators
+            .iter()
+            .map(|indicator| self.variable(*indicator))
+            .filter(|rendered| !rendered.is_empty())
+            .collect::<Vec<_>>()
+            .join(&self.shell.escape(&self.config.git_status.separator))
+    }
+
+    /// Renders an indicator's symbol (and count, if configured) if its counter is non-zero
+    fn indicator<F>(&self, count: F, indicator: &IndicatorConfig) -> String
+    where
+        F: Fn(&GitRepoState) -> usize,
+    {
+        let count = match self.git_state {
+            Some(state) => count(state),
+            None => 0,
+        };
+        if count == 0 || indicator.symbol.is_empty() {
+            return String::new();
+        }
+        if indicator.show_count {
+            self.paint_element(indicator, &format!("{}{count}", indicator.symbol))
+        } else {
+            self.paint_element(indicator, &indicator.symbol)
+        }
+    }
+
+    /// Applies an element's style to `text`
+    fn paint_element(&self, element: &IndicatorConfig, text: &str) -> String {
+        self.paint(element.ansi_style(), text)
+    }
+
+    /// Applies `style` to `text`, leaving empty text empty
+    fn paint(&self, style: Style, text: &str) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        format!(
+            "{}{}{}",
+            self.shell.wrap_non_printing(&style.prefix().to_string()),
+            self.shell.escape(text),
+            self.shell.wrap_non_printing(&style.suffix().to_string()),
+        )
+    }
+}
+
+/// Shortens every directory but the last to its first character, keeping the leading
+/// dot of hidden directories (`~/.config/gprompt` becomes `~/.c/gprompt`)
+pub fn abbreviate_path(path: &str) -> String {
+    let components: Vec<&str> = path.split('/').collect();
+    let last = components.len().saturating_sub(1);
+    components
+        .iter()
+        .enumerate()
+        .map(|(i, component)| {
+            if i == last || *component == "~" {
+                return component.to_string();
+            }
+            let keep = if component.starts_with('.') { 2 } else { 1 };
+            component.chars().take(keep).collect()
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// Formats a duration in milliseconds as `850ms`, `4s`, `2m5s` or `1h2m3s`
+pub fn format_duration(ms: u64) -> String {
+    if ms < 1000 {
+        return format!("{ms}ms");
+    }
+    let secs = ms / 1000;
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h{minutes}m{seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
@@ -0,0 +1,70 @@
+// src/services/shell_init.rs
+//! Shell integration scripts printed by `gprompt init <shell>`.
+//!
+//! The scripts hook into the shell's prompt machinery and pass the last exit status,
+//! command duration, job count and terminal width to gprompt, so they can be shown in
+//! the prompt. They are meant to be evaluated at shell startup:
+//!
+//! ```sh
+//! eval "$(gprompt init zsh)"
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use gprompt::models::shell::Shell;
+//! use gprompt::services::shell_init::init_script;
+//!
+//! let script = init_script(Shell::Zsh, "/usr/local/bin/gprompt", false).unwrap();
+//! assert!(script.contains("__gprompt_bin='/usr/local/bin/gprompt'"));
+//! assert!(init_script(Shell::Plain, "gprompt", false).is_none());
+//! ```
+
+use crate::models::shell::Shell;
+
+const BASH_INIT: &str = include_str!("../init/gprompt.bash");
+const ZSH_INIT: &str = include_str!("../init/gprompt.zsh");
+const FISH_INIT: &str = include_str!("../init/gprompt.fish");
+
+const ZSH_RIGHT_PROMPT: &str = "RPROMPT='$(\"$__gprompt_bin\" \"${__gprompt_args[@]}\" --right)'\n";
+const FISH_RIGHT_PROMPT: &str = "
+function fish_right_prompt
+    set -l gprompt_status $status
+    ::GPROMPT:: --shell fish --right --status $gprompt_status --duration $CMD_DURATION --jobs (count (jobs -p)) --width $COLUMNS
+end
+";
+
+/// Placeholder in the scripts for the path to the gprompt binary
+const PLACEHOLDER: &str = "::GPROMPT::";
+
+/// Returns the integration script for `shell`
+///
+/// # Arguments
+/// * `shell` - Shell to generate the script for
+/// * `executable` - Path to the gprompt binary, as it should be invoked by the script
+/// * `right_prompt` - Whether to also set up a right-hand prompt (ignored for bash,
+///   which has none)
+///
+/// # Returns
+/// * `Some(String)` - The script
+/// * `None` - If `shell` has no integration (`Shell::Plain`)
+pub fn init_script(shell: Shell, executable: &str, right_prompt: bool) -> Option<String> {
+    let (script, right) = match shell {
+        Shell::Bash => (BASH_INIT, ""),
+        Shell::Zsh => (ZSH_INIT, ZSH_RIGHT_PROMPT),
+        Shell::Fish => (FISH_INIT, FISH_RIGHT_PROMPT),
+        Shell::Plain => return None,
+    };
+    let mut script = script.to_string();
+    if right_prompt {
+        script.push_str(right);
+    }
+    let quoted = match shell {
+        Shell::Fish => format!(
+            "'{}'",
+            executable.replace('\\', "\\\\").replace('\'', "\\'")
+        ),
+        _ => format!("'{}'", executable.replace('\'', "'\\''")),
+    };
+    Some(script.replace(PLACEHOLDER, &quoted))
+}
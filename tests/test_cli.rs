@@ -1,9 +1,21 @@
 use assert_cmd::Command;
+use predicates::prelude::*;
 use predicates::str::contains;
 use std::fs;
 use std::process::Command as StdCommand;
 use tempfile::TempDir;
 
+/// Builds a `gprompt` command that ignores the user's config file and daemon
+fn gprompt() -> Command {
+    // Deprecated in assert_cmd 2.1 in favour of `cargo_bin_cmd!`, which 2.0 lacks
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    cmd.env("GPROMPT_CONFIG", "/nonexistent/gprompt/config.toml")
+        .env_remove("XDG_CONFIG_HOME")
+        .env("GPROMPT_SOCKET", "/nonexistent/gprompt.sock");
+    cmd
+}
+
 fn init_git_repo(path: &std::path::Path) {
     StdCommand::new("git")
         .arg("init")
@@ -27,7 +39,7 @@ fn init_git_repo(path: &std::path::Path) {
 fn prompt_shows_branch_clean_repo() {
     let tmp = TempDir::new().unwrap();
     init_git_repo(tmp.path());
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
     // Should show branch name, but no status symbols
     cmd.assert().stdout(contains(" "));
@@ -38,7 +50,7 @@ fn prompt_shows_untracked_file() {
     let tmp = TempDir::new().unwrap();
     init_git_repo(tmp.path());
     fs::write(tmp.path().join("foo.txt"), "bar").unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
     cmd.assert().stdout(contains("*"));
 }
@@ -54,7 +66,7 @@ fn prompt_shows_staged_file() {
         .current_dir(tmp.path())
         .output()
         .unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
     cmd.assert().stdout(contains("+"));
 }
@@ -78,7 +90,7 @@ fn prompt_shows_unstaged_changes() {
         .unwrap();
     // Modify the file (unstaged change)
     fs::write(&file, "baz").unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
     cmd.assert().stdout(contains("×"));
 }
@@ -106,7 +118,7 @@ fn prompt_shows_stashed_changes() {
         .current_dir(tmp.path())
         .output()
         .unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
     cmd.assert().stdout(contains("•"));
 }
@@ -183,7 +195,7 @@ fn prompt_shows_ahead_of_remote() {
         .current_dir(c1.path())
         .output()
         .unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(c1.path());
     cmd.assert().stdout(contains("↑"));
 }
@@ -238,7 +250,7 @@ fn prompt_shows_behind_remote() {
         .current_dir(c1.path())
         .output()
         .unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(c1.path());
     cmd.assert().stdout(contains("↓"));
 }
@@ -306,7 +318,7 @@ fn prompt_shows_ahead_and_behind() {
         .current_dir(c1.path())
         .output()
         .unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(c1.path());
     let out = cmd.assert().get_output().stdout.clone();
     let s = String::from_utf8_lossy(&out);
@@ -365,7 +377,7 @@ fn prompt_shows_all_states() {
     fs::write(c1.path().join("untracked.txt"), "u").unwrap();
     // Unstaged change (after stash so it remains unstaged)
     fs::write(&file, "unstaged_content").unwrap();
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(c1.path());
     let out = cmd.assert().get_output().stdout.clone();
     let s = String::from_utf8_lossy(&out);
@@ -459,11 +471,12 @@ fn test_rebase_state_detection() {
     let tmp = TempDir::new().unwrap();
     create_repo_in_rebase_state(tmp.path());
 
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
 
-    // Should display "Rebasing" in the output
-    cmd.assert().stdout(contains("Rebasing"));
+    // Should display the rebased branch, its new base and progress
+    cmd.assert()
+        .stdout(contains("Rebasing feature onto master 1/2"));
 }
 
 // T030: Integration test helper to create repository in cherry-pick state
@@ -579,11 +592,11 @@ fn test_cherry_pick_state_detection() {
     let tmp = TempDir::new().unwrap();
     create_repo_in_cherry_pick_state(tmp.path());
 
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
 
-    // Should display "Cherry-picking" in the output
-    cmd.assert().stdout(contains("Cherry-picking"));
+    // Stopped at the conflict in the second of three commits
+    cmd.assert().stdout(contains("Cherry-picking 2/3"));
 }
 
 // T038: Integration test helper to create repository in detached HEAD state
@@ -641,7 +654,7 @@ fn test_detached_head_state_detection() {
     let tmp = TempDir::new().unwrap();
     let short_sha = create_repo_in_detached_head_state(tmp.path());
 
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
 
     let out = cmd.assert().get_output().stdout.clone();
@@ -730,11 +743,34 @@ fn test_merge_state_detection() {
     let tmp = TempDir::new().unwrap();
     create_repo_in_merge_state(tmp.path());
 
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
 
-    // Should display "Merging" in the output
-    cmd.assert().stdout(contains("Merging"));
+    // Should display the branch being merged in
+    cmd.assert().stdout(contains("Merging feature"));
+}
+
+#[test]
+fn test_merge_with_custom_message_names_branch() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+    commit_file(path, "file.txt", "initial\n");
+    git(path, &["checkout", "-b", "feature/x"]);
+    commit_file(path, "file.txt", "FEATURE\n");
+    git(path, &["checkout", "master"]);
+    commit_file(path, "file.txt", "MASTER\n");
+    let _ = StdCommand::new("git")
+        .args(["merge", "-m", "Bring in the feature", "feature/x"])
+        .current_dir(path)
+        .output();
+
+    // MERGE_MSG no longer names the branch, so it is found from MERGE_HEAD
+    let message = fs::read_to_string(path.join(".git/MERGE_MSG")).unwrap();
+    assert!(message.starts_with("Bring in the feature"));
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert().stdout(contains("Merging feature/x"));
 }
 
 // T053: Integration test helper to create repository in revert state
@@ -798,23 +834,421 @@ fn test_revert_state_detection() {
     let tmp = TempDir::new().unwrap();
     create_repo_in_revert_state(tmp.path());
 
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
 
     // Should display "Reverting" in the output
     cmd.assert().stdout(contains("Reverting"));
 }
 
+#[test]
+fn test_revert_sequence_progress() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+
+    for (file, contents, message) in [
+        ("file.txt", "line 1\n", "initial commit"),
+        ("file.txt", "MODIFIED line 1\n", "change to revert"),
+        ("other.txt", "other\n", "clean change to revert"),
+        ("file.txt", "CONFLICTING line 1\n", "conflicting commit"),
+    ] {
+        fs::write(path.join(file), contents).unwrap();
+        StdCommand::new("git")
+            .args(["add", file])
+            .current_dir(path)
+            .output()
+            .unwrap();
+        StdCommand::new("git")
+            .args(["commit", "-m", message])
+            .current_dir(path)
+            .output()
+            .unwrap();
+    }
+
+    // The first revert applies cleanly, the second stops at a conflict
+    let _ = StdCommand::new("git")
+        .args(["revert", "--no-edit", "HEAD~1", "HEAD~2"])
+        .current_dir(path)
+        .output();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert().stdout(contains("Reverting 2/2"));
+}
+
 // T064: Test for missing .git directory (non-git repository)
 #[test]
 fn test_non_git_directory() {
     let tmp = TempDir::new().unwrap();
     // Don't initialize git - just create an empty directory
 
-    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    let mut cmd = gprompt();
     cmd.current_dir(tmp.path());
 
     // Should handle gracefully - output should be empty or show error message
     // The program should not panic
     cmd.assert().success();
 }
+
+#[test]
+fn test_config_file_overrides_symbols() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    fs::write(tmp.path().join("foo.txt"), "bar").unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(
+        &config,
+        "[git_status.untracked]\nsymbol = \"?\"\n\n[character]\nsymbol = \">\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).env("GPROMPT_CONFIG", &config);
+
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let s = String::from_utf8_lossy(&out);
+    assert!(s.contains('?'), "Expected '?' in output: {}", s);
+    assert!(s.contains('>'), "Expected '>' in output: {}", s);
+    assert!(!s.contains('❯'), "Unexpected '❯' in output: {}", s);
+}
+
+#[test]
+fn test_invalid_config_falls_back_to_defaults() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "[character]\ncolour = \"mauve\"\n").unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).env("GPROMPT_CONFIG", &config);
+
+    cmd.assert()
+        .success()
+        .stdout(contains("❯"))
+        .stderr(contains("invalid configuration"));
+}
+
+#[test]
+fn test_format_from_config() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    // Keep the config file outside the repository so it isn't counted as untracked
+    let config_dir = TempDir::new().unwrap();
+    let config = config_dir.path().join("config.toml");
+    fs::write(
+        &config,
+        "format = \"[($git_branch)] $character\"\nright_format = \"<$git_status>\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).env("GPROMPT_CONFIG", &config);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let s = String::from_utf8_lossy(&out);
+    assert!(s.starts_with("[]"), "Unexpected output: {}", s);
+    assert!(!s.contains('\n'), "Unexpected newline in output: {}", s);
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path())
+        .env("GPROMPT_CONFIG", &config)
+        .arg("--right");
+    cmd.assert().success().stdout("<>");
+}
+
+#[test]
+fn test_shell_bash_wraps_colour_codes() {
+    let tmp = TempDir::new().unwrap();
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).args(["--shell", "bash"]);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let s = String::from_utf8_lossy(&out);
+    assert!(
+        s.contains("\\[\x1b[35m\\]❯\\[\x1b[0m\\]"),
+        "Expected wrapped colour codes in output: {:?}",
+        s
+    );
+}
+
+#[test]
+fn test_shell_zsh_wraps_colour_codes() {
+    let tmp = TempDir::new().unwrap();
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).arg("--shell=zsh");
+    cmd.assert()
+        .success()
+        .stdout(contains("%{\x1b[35m%}❯%{\x1b[0m%}"));
+}
+
+#[test]
+fn test_unknown_shell_is_rejected() {
+    let tmp = TempDir::new().unwrap();
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).args(["--shell", "tcsh"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("unknown shell \"tcsh\""));
+}
+
+#[test]
+fn test_init_prints_shell_integration() {
+    for (shell, expected) in [
+        ("bash", "PROMPT_COMMAND"),
+        ("zsh", "add-zsh-hook precmd"),
+        ("fish", "function fish_prompt"),
+    ] {
+        let mut cmd = gprompt();
+        cmd.args(["init", shell]);
+        cmd.assert()
+            .success()
+            .stdout(contains(expected))
+            .stdout(contains(format!("--shell {shell}")));
+    }
+}
+
+#[test]
+fn test_init_rejects_unknown_shell() {
+    let mut cmd = gprompt();
+    cmd.args(["init", "plain"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("unknown shell \"plain\""));
+
+    let mut cmd = gprompt();
+    cmd.arg("init");
+    cmd.assert()
+        .code(2)
+        .stderr(contains("init requires a shell"));
+}
+
+#[test]
+fn test_shell_context_flags() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "format = \"$status|$duration|$jobs\"\n").unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path())
+        .env("GPROMPT_CONFIG", &config)
+        .args([
+            "--status",
+            "1",
+            "--duration=2500",
+            "--jobs",
+            "3",
+            "--width",
+            "80",
+        ]);
+    cmd.assert()
+        .success()
+        .stdout("\x1b[31m✘1\x1b[0m|\x1b[33m2s\x1b[0m|\x1b[34m✦3\x1b[0m");
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).args(["--jobs", "many"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("--jobs expects a number"));
+}
+
+#[test]
+fn test_status_json() {
+    let tmp = TempDir::new().unwrap();
+    create_repo_in_rebase_state(tmp.path());
+    fs::write(tmp.path().join("notes.txt"), "untracked").unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).args(["status", "--json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(report["version"], 1);
+    assert_eq!(
+        report["path"],
+        tmp.path().canonicalize().unwrap().display().to_string()
+    );
+    let state = &report["state"];
+    assert_eq!(state["untracked"], 1);
+    assert_eq!(state["conflicted"], 1);
+    let special_state = &state["special_state"];
+    assert_eq!(special_state["kind"], "rebasing");
+    assert_eq!(special_state["details"]["head_name"], "feature");
+    assert_eq!(special_state["details"]["onto"], "master");
+    assert_eq!(special_state["details"]["progress"]["current"], 1);
+    assert_eq!(special_state["details"]["progress"]["total"], 2);
+}
+
+#[test]
+fn test_status_json_outside_repository() {
+    let tmp = TempDir::new().unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).args(["status", "--json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["version"], 1);
+    assert!(report["state"].is_null());
+
+    let mut cmd = gprompt();
+    cmd.current_dir(tmp.path()).arg("status");
+    cmd.assert()
+        .code(2)
+        .stderr(contains("status requires --json"));
+}
+
+fn git(path: &std::path::Path, args: &[&str]) {
+    StdCommand::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .unwrap();
+}
+
+fn commit_file(path: &std::path::Path, name: &str, contents: &str) {
+    fs::write(path.join(name), contents).unwrap();
+    git(path, &["add", name]);
+    git(path, &["commit", "-m", &format!("change {name}")]);
+}
+
+#[test]
+fn test_am_progress() {
+    let tmp = TempDir::new().unwrap();
+    let patches = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+    commit_file(path, "file.txt", "initial\n");
+
+    // Three patches, the third of which conflicts
+    commit_file(path, "a.txt", "a\n");
+    commit_file(path, "b.txt", "b\n");
+    commit_file(path, "file.txt", "PATCHED\n");
+    let output_dir = patches.path().to_str().unwrap();
+    git(path, &["format-patch", "-3", "-o", output_dir]);
+    git(path, &["reset", "--hard", "HEAD~3"]);
+    commit_file(path, "file.txt", "CONFLICTING\n");
+
+    let mut mailbox: Vec<_> = fs::read_dir(patches.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    mailbox.sort();
+    let _ = StdCommand::new("git")
+        .arg("am")
+        .args(&mailbox)
+        .current_dir(path)
+        .output();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert().stdout(contains("Applying patches 3/3"));
+
+    // Older versions of git don't mark the directory as belonging to `git am`
+    fs::remove_file(path.join(".git/rebase-apply/applying")).unwrap();
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert().stdout(contains("Applying patches 3/3"));
+}
+
+#[test]
+fn test_apply_backend_rebase_is_not_am() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+    commit_file(path, "file.txt", "initial\n");
+    git(path, &["checkout", "-b", "feature"]);
+    commit_file(path, "file.txt", "FEATURE\n");
+    commit_file(path, "other.txt", "other\n");
+    git(path, &["checkout", "master"]);
+    commit_file(path, "file.txt", "MASTER\n");
+    git(path, &["checkout", "feature"]);
+    let _ = StdCommand::new("git")
+        .args(["rebase", "--apply", "master"])
+        .current_dir(path)
+        .output();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert()
+        .stdout(contains("Rebasing feature onto master 1/2"));
+}
+
+#[test]
+fn test_bisect_details() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+    for i in 0..=40 {
+        commit_file(path, "file.txt", &format!("{i}\n"));
+    }
+    git(path, &["bisect", "start", "HEAD", "HEAD~40"]);
+    git(path, &["bisect", "good"]);
+    git(path, &["bisect", "bad"]);
+    let candidate = StdCommand::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    let candidate = String::from_utf8_lossy(&candidate.stdout)
+        .trim()
+        .to_string();
+
+    // git reports "4 revisions left to test after this (roughly 2 steps)"
+    let mut cmd = gprompt();
+    cmd.current_dir(path).args(["status", "--json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let details = &report["state"]["special_state"]["details"];
+    assert_eq!(report["state"]["special_state"]["kind"], "bisecting");
+    assert_eq!(details["good"], 2);
+    assert_eq!(details["bad"], 2);
+    assert_eq!(details["remaining"], 10);
+    assert_eq!(details["steps"], 2);
+    assert_eq!(details["candidate"], candidate.as_str());
+
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert()
+        .stdout(contains(format!("Bisecting ~2 steps @ {candidate}")));
+}
+
+#[test]
+fn test_detached_head_names_tag_or_description() {
+    let tmp = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+    commit_file(path, "file.txt", "1\n");
+    git(path, &["tag", "v1.0"]);
+    commit_file(path, "file.txt", "2\n");
+    commit_file(path, "file.txt", "3\n");
+
+    // Exactly at a tag
+    git(path, &["checkout", "v1.0"]);
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert().stdout(contains("Detached @ v1.0"));
+
+    // Two commits past it
+    git(path, &["checkout", "master"]);
+    git(path, &["checkout", "--detach"]);
+    let description = StdCommand::new("git")
+        .args(["describe", "--tags"])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    let description = String::from_utf8_lossy(&description.stdout)
+        .trim()
+        .to_string();
+    assert!(description.starts_with("v1.0-2-g"));
+    let mut cmd = gprompt();
+    cmd.current_dir(path);
+    cmd.assert()
+        .stdout(contains(format!("Detached @ {description}")));
+
+    // A lightweight tag doesn't count when only annotated tags may be used
+    let config = config_dir.path().join("config.toml");
+    fs::write(&config, "[detached]\ndescribe = \"annotated\"\n").unwrap();
+    let mut cmd = gprompt();
+    cmd.current_dir(path).env("GPROMPT_CONFIG", &config);
+    cmd.assert()
+        .stdout(contains("Detached @ "))
+        .stdout(contains("v1.0").not());
+}
@@ -0,0 +1,142 @@
+use ansi_term::Colour;
+use gprompt::models::prompt_config::{
+    ColourSpec, DescribeStrategy, IgnoreSubmodules, PromptConfig, StyleAttributes,
+};
+use gprompt::services::config_loader::{load_config_from, parse_config};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_empty_config_matches_defaults() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config, PromptConfig::default());
+}
+
+#[test]
+fn test_default_styles_match_builtin_look() {
+    let config = PromptConfig::default();
+    assert_eq!(config.path.ansi_style(), Colour::Blue.normal());
+    assert_eq!(config.git_branch.ansi_style(), Colour::White.dimmed());
+    assert_eq!(config.character.ansi_style(), Colour::Purple.normal());
+    assert_eq!(config.character.symbol, "❯");
+    assert_eq!(
+        config.git_status.unstaged.ansi_style(),
+        Colour::Red.normal()
+    );
+    assert_eq!(config.git_status.stashed.symbol, "•");
+}
+
+#[test]
+fn test_partial_indicator_keeps_other_defaults() {
+    let config = parse_config(
+        r##"
+        [git_status.ahead]
+        symbol = "⇡"
+
+        [git_status.untracked]
+        colour = "#ff8800"
+        style = "bold underline"
+        "##,
+    )
+    .unwrap();
+    assert_eq!(config.git_status.ahead.symbol, "⇡");
+    assert_eq!(
+        config.git_status.ahead.colour,
+        ColourSpec::Named("cyan".to_string())
+    );
+    assert_eq!(config.git_status.untracked.symbol, "*");
+    assert_eq!(
+        config.git_status.untracked.ansi_style(),
+        Colour::RGB(0xff, 0x88, 0x00).bold().underline()
+    );
+}
+
+#[test]
+fn test_colour_parsing() {
+    let parse = |s: &str| ColourSpec::try_from(s.to_string());
+    assert_eq!(
+        parse("Magenta"),
+        Ok(ColourSpec::Named("magenta".to_string()))
+    );
+    assert_eq!(parse("208"), Ok(ColourSpec::Fixed(208)));
+    assert_eq!(parse("#0a0B0c"), Ok(ColourSpec::Rgb(10, 11, 12)));
+    assert_eq!(parse("default"), Ok(ColourSpec::Default));
+    assert!(parse("mauve").is_err());
+    assert!(parse("256").is_err());
+    assert!(parse("#12345").is_err());
+}
+
+#[test]
+fn test_style_attributes_parsing() {
+    let attributes = StyleAttributes::try_from("bold dimmed".to_string()).unwrap();
+    assert!(attributes.bold && attributes.dimmed && !attributes.italic);
+    assert!(StyleAttributes::try_from("sparkly".to_string()).is_err());
+}
+
+#[test]
+fn test_invalid_config_is_rejected() {
+    assert!(parse_config("[git_status.ahead]\ncolour = \"mauve\"\n").is_err());
+    assert!(parse_config("[git_status.bogus]\nsymbol = \"?\"\n").is_err());
+    assert!(parse_config("not toml").is_err());
+}
+
+#[test]
+fn test_missing_config_file_uses_defaults() {
+    let tmp = TempDir::new().unwrap();
+    let config = load_config_from(&tmp.path().join("missing.toml")).unwrap();
+    assert_eq!(config, PromptConfig::default());
+}
+
+#[test]
+fn test_config_file_is_loaded() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("config.toml");
+    fs::write(&path, "[character]\nsymbol = \"$\"\n").unwrap();
+    let config = load_config_from(&path).unwrap();
+    assert_eq!(config.character.symbol, "$");
+}
+
+#[test]
+fn test_format_is_parsed_at_load() {
+    let config = parse_config("format = \"$path $character\"\n").unwrap();
+    assert_eq!(config.format.source(), "$path $character");
+    assert!(parse_config("format = \"$unknown\"\n").is_err());
+}
+
+#[test]
+fn test_show_count_is_per_indicator() {
+    let config = parse_config("[git_status.ahead]\nshow_count = true\n").unwrap();
+    assert!(config.git_status.ahead.show_count);
+    assert!(!config.git_status.behind.show_count);
+}
+
+#[test]
+fn test_detached_describe_setting() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.detached.describe, DescribeStrategy::Tags);
+    assert_eq!(config.detached.timeout, 50);
+
+    let config = parse_config("[detached]\ndescribe = \"off\"\n").unwrap();
+    assert_eq!(config.detached.describe, DescribeStrategy::Off);
+    assert_eq!(config.detached.timeout, 50);
+
+    assert!(parse_config("[detached]\ndescribe = \"sometimes\"\n").is_err());
+}
+
+#[test]
+fn test_ignore_submodules_setting() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.ignore_submodules, IgnoreSubmodules::Git);
+
+    let config = parse_config("ignore_submodules = \"dirty\"\n").unwrap();
+    assert_eq!(config.ignore_submodules, IgnoreSubmodules::Dirty);
+}
+
+#[test]
+fn test_non_ascii_hex_colour_is_rejected() {
+    // Six bytes, but not six characters: must be an error, not a panic
+    let parse = |s: &str| ColourSpec::try_from(s.to_string());
+    assert!(parse("#a€bc").is_err());
+    assert!(parse("#+f+f+f").is_err());
+    assert!(parse_config("[path]\ncolour = \"#a€bc\"\n").is_err());
+}
@@ -0,0 +1,233 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use gprompt::services::config_loader::CONFIG_ENV_VAR;
+use gprompt::services::daemon::{query_daemon, SOCKET_ENV_VAR};
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command as StdCommand};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const NO_CONFIG: &str = "/nonexistent/gprompt/config.toml";
+
+/// Builds a `gprompt` command that ignores the user's config file
+fn gprompt() -> Command {
+    // Deprecated in assert_cmd 2.1 in favour of `cargo_bin_cmd!`, which 2.0 lacks
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("gprompt").unwrap();
+    cmd.env(CONFIG_ENV_VAR, NO_CONFIG)
+        .env_remove("XDG_CONFIG_HOME");
+    cmd
+}
+
+fn git(path: &Path, args: &[&str]) {
+    StdCommand::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .unwrap();
+}
+
+fn init_git_repo(path: &Path) {
+    git(path, &["init"]);
+    // Set user config to avoid git warnings
+    git(path, &["config", "user.email", "test@example.com"]);
+    git(path, &["config", "user.name", "Test User"]);
+    fs::write(path.join("a.txt"), "a").unwrap();
+    git(path, &["add", "a.txt"]);
+    git(path, &["commit", "-m", "add a.txt"]);
+}
+
+/// A daemon process listening on a socket in its own temporary directory, killed on drop
+struct Daemon {
+    child: Child,
+    socket: PathBuf,
+    _dir: TempDir,
+}
+
+impl Daemon {
+    fn start() -> Self {
+        Self::with_config(None)
+    }
+
+    /// Starts a daemon that reads `config` instead of the default configuration
+    fn with_config(config: Option<&str>) -> Self {
+        let dir = TempDir::new().unwrap();
+        let socket = dir.path().join("gprompt.sock");
+        let config_path = match config {
+            Some(config) => {
+                let path = dir.path().join("config.toml");
+                fs::write(&path, config).unwrap();
+                path
+            }
+            None => PathBuf::from(NO_CONFIG),
+        };
+        let child = StdCommand::new(env!("CARGO_BIN_EXE_gprompt"))
+            .arg("daemon")
+            .env(SOCKET_ENV_VAR, &socket)
+            .env(CONFIG_ENV_VAR, config_path)
+            .env_remove("XDG_CONFIG_HOME")
+            .spawn()
+            .unwrap();
+        let started = Instant::now();
+        // The socket file appears just before the daemon starts listening on it
+        while UnixStream::connect(&socket).is_err() {
+            assert!(started.elapsed() < TIMEOUT, "daemon did not start");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Self {
+            child,
+            socket,
+            _dir: dir,
+        }
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_daemon_answers_queries() {
+    let daemon = Daemon::start();
+    let repo = TempDir::new().unwrap();
+    init_git_repo(repo.path());
+    fs::write(repo.path().join("b.txt"), "b").unwrap();
+
+    let state = query_daemon(&daemon.socket, repo.path(), TIMEOUT)
+        .expect("daemon answers")
+        .expect("directory is a repository");
+    assert_eq!(state.untracked, 1);
+    assert!(!state.status_timed_out);
+}
+
+#[test]
+fn test_daemon_answers_outside_repository() {
+    let daemon = Daemon::start();
+    let dir = TempDir::new().unwrap();
+
+    let answer = query_daemon(&daemon.socket, dir.path(), TIMEOUT);
+    assert_eq!(answer, Some(None));
+}
+
+#[test]
+fn test_daemon_notices_changes() {
+    let daemon = Daemon::start();
+    let repo = TempDir::new().unwrap();
+    init_git_repo(repo.path());
+
+    let state = query_daemon(&daemon.socket, repo.path(), TIMEOUT)
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.unstaged, 0);
+
+    fs::write(repo.path().join("a.txt"), "a2").unwrap();
+    let started = Instant::now();
+    loop {
+        let state = query_daemon(&daemon.socket, repo.path(), TIMEOUT)
+            .unwrap()
+            .unwrap();
+        if state.unstaged == 1 {
+            break;
+        }
+        assert!(started.elapsed() < TIMEOUT, "daemon kept a stale state");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_daemon_watches_new_directories() {
+    let daemon = Daemon::start();
+    let repo = TempDir::new().unwrap();
+    init_git_repo(repo.path());
+    query_daemon(&daemon.socket, repo.path(), TIMEOUT)
+        .unwrap()
+        .unwrap();
+
+    let wait_for = |check: &dyn Fn(&gprompt::models::git_repo_state::GitRepoState) -> bool| {
+        let started = Instant::now();
+        loop {
+            let state = query_daemon(&daemon.socket, repo.path(), TIMEOUT)
+                .unwrap()
+                .unwrap();
+            if check(&state) {
+                break;
+            }
+            assert!(started.elapsed() < TIMEOUT, "daemon kept a stale state");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    };
+
+    fs::create_dir(repo.path().join("new")).unwrap();
+    fs::write(repo.path().join("new/b.txt"), "b").unwrap();
+    git(repo.path(), &["add", "new/b.txt"]);
+    wait_for(&|state| state.staged == 1);
+
+    // Only a watch on the new directory sees this
+    fs::write(repo.path().join("new/b.txt"), "b2").unwrap();
+    wait_for(&|state| state.unstaged == 1);
+}
+
+#[test]
+fn test_second_daemon_refuses_to_start() {
+    let daemon = Daemon::start();
+
+    let mut cmd = gprompt();
+    cmd.arg("daemon")
+        .env(SOCKET_ENV_VAR, &daemon.socket)
+        .assert()
+        .failure();
+}
+
+/// Creates a repository with a detached HEAD one commit past the tag `v1`, which the
+/// default configuration describes as `v1-1-g...`
+fn init_detached_repo(path: &Path) {
+    init_git_repo(path);
+    git(path, &["tag", "v1"]);
+    fs::write(path.join("a.txt"), "a2").unwrap();
+    git(path, &["commit", "-am", "change a.txt"]);
+    git(path, &["checkout", "--detach"]);
+}
+
+#[test]
+fn test_prompt_uses_daemon() {
+    // The daemon's configuration differs from the prompt's, so its answer shows
+    let daemon = Daemon::with_config(Some("[detached]\ndescribe = \"off\"\n"));
+    let repo = TempDir::new().unwrap();
+    init_detached_repo(repo.path());
+    // Have the state cached, and give the prompt time to ask for it on a busy machine
+    query_daemon(&daemon.socket, repo.path(), TIMEOUT).unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let config = config_dir.path().join("config.toml");
+    fs::write(&config, "daemon_timeout = 5000\n").unwrap();
+
+    let mut cmd = gprompt();
+    cmd.current_dir(repo.path())
+        .env(SOCKET_ENV_VAR, &daemon.socket)
+        .env(CONFIG_ENV_VAR, &config)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Detached @ "))
+        .stdout(predicates::str::contains("v1").not());
+}
+
+#[test]
+fn test_prompt_falls_back_without_daemon() {
+    let dir = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    init_detached_repo(repo.path());
+
+    let mut cmd = gprompt();
+    cmd.current_dir(repo.path())
+        .env(SOCKET_ENV_VAR, dir.path().join("missing.sock"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Detached @ v1-1-g"));
+}
@@ -1,16 +1,23 @@
-use gprompt::models::git_repo_state::GitRepoState;
-use gprompt::models::git_special_state::{GitSpecialState, OperationProgress};
+use gprompt::models::git_repo_state::{ChangeCounts, GitRepoState, SubmoduleCounts, UpstreamState};
+use gprompt::models::git_special_state::{
+    BisectInfo, GitSpecialState, OperationProgress, RebaseInfo,
+};
 
 #[test]
 fn test_git_repo_state_default() {
     let state = GitRepoState::default();
     assert!(state.branch.is_none());
+    assert!(state.upstream.is_none());
     assert_eq!(state.ahead, 0);
     assert_eq!(state.behind, 0);
+    assert_eq!(state.push_ahead, 0);
+    assert_eq!(state.push_behind, 0);
     assert_eq!(state.staged, 0);
     assert_eq!(state.unstaged, 0);
     assert_eq!(state.untracked, 0);
     assert_eq!(state.stashed, 0);
+    assert_eq!(state.conflicted, 0);
+    assert!(!state.status_timed_out);
 }
 
 #[test]
@@ -19,19 +26,34 @@ fn test_git_repo_state_custom() {
         branch: Some("main".to_string()),
         ahead: 2,
         behind: 1,
+        push_ahead: 8,
+        push_behind: 9,
+        default_branch: None,
+        worktree: None,
+        prunable_worktrees: 0,
+        superproject: None,
+        submodules: SubmoduleCounts::default(),
         staged: 3,
         unstaged: 4,
+        staged_changes: ChangeCounts::default(),
+        unstaged_changes: ChangeCounts::default(),
         untracked: 5,
         stashed: 6,
+        conflicted: 7,
+        status_timed_out: false,
         special_state: GitSpecialState::Normal,
+        upstream: Some(UpstreamState::Tracking("origin/main".to_string())),
     };
     assert_eq!(state.branch.as_deref(), Some("main"));
     assert_eq!(state.ahead, 2);
     assert_eq!(state.behind, 1);
+    assert_eq!(state.push_ahead, 8);
+    assert_eq!(state.push_behind, 9);
     assert_eq!(state.staged, 3);
     assert_eq!(state.unstaged, 4);
     assert_eq!(state.untracked, 5);
     assert_eq!(state.stashed, 6);
+    assert_eq!(state.conflicted, 7);
 }
 
 #[test]
@@ -55,16 +77,43 @@ fn test_operation_progress_validation() {
 #[test]
 fn test_rebasing_display_name_with_progress() {
     let progress = OperationProgress::new(3, 7).unwrap();
-    let state = GitSpecialState::Rebasing(Some(progress));
+    let state = GitSpecialState::Rebasing(RebaseInfo {
+        progress: Some(progress),
+        ..Default::default()
+    });
     assert_eq!(state.display_name(), Some("Rebasing 3/7".to_string()));
 }
 
 #[test]
 fn test_rebasing_display_name_without_progress() {
-    let state = GitSpecialState::Rebasing(None);
+    let state = GitSpecialState::Rebasing(RebaseInfo::default());
     assert_eq!(state.display_name(), Some("Rebasing".to_string()));
 }
 
+#[test]
+fn test_rebasing_display_name_with_branches() {
+    let mut info = RebaseInfo {
+        head_name: Some("feature/x".to_string()),
+        onto: Some("main".to_string()),
+        progress: OperationProgress::new(3, 7),
+    };
+    let state = GitSpecialState::Rebasing(info.clone());
+    assert_eq!(
+        state.display_name(),
+        Some("Rebasing feature/x onto main 3/7".to_string())
+    );
+
+    // Rebasing a detached HEAD onto a commit no branch points at
+    info.head_name = None;
+    info.onto = Some("a1b2c3d".to_string());
+    info.progress = None;
+    let state = GitSpecialState::Rebasing(info);
+    assert_eq!(
+        state.display_name(),
+        Some("Rebasing onto a1b2c3d".to_string())
+    );
+}
+
 #[test]
 fn test_cherry_picking_display_name_with_progress() {
     let progress = OperationProgress::new(2, 4).unwrap();
@@ -104,24 +153,88 @@ fn test_detached_sha_length() {
 
 #[test]
 fn test_merging_display_name() {
-    let state = GitSpecialState::Merging;
+    let state = GitSpecialState::Merging(None);
     assert_eq!(state.display_name(), Some("Merging".to_string()));
+
+    let state = GitSpecialState::Merging(Some("feature/x".to_string()));
+    assert_eq!(state.display_name(), Some("Merging feature/x".to_string()));
 }
 
 #[test]
 fn test_reverting_display_name() {
-    let state = GitSpecialState::Reverting;
+    let state = GitSpecialState::Reverting(None);
     assert_eq!(state.display_name(), Some("Reverting".to_string()));
+
+    let state = GitSpecialState::Reverting(OperationProgress::new(2, 3));
+    assert_eq!(state.display_name(), Some("Reverting 2/3".to_string()));
 }
 
 #[test]
 fn test_bisecting_display_name() {
-    let state = GitSpecialState::Bisecting;
+    let state = GitSpecialState::Bisecting(BisectInfo::default());
     assert_eq!(state.display_name(), Some("Bisecting".to_string()));
+
+    let state = GitSpecialState::Bisecting(BisectInfo {
+        good: 1,
+        bad: 1,
+        remaining: Some(20),
+        steps: Some(4),
+        candidate: Some("a1b2c3d".to_string()),
+    });
+    assert_eq!(
+        state.display_name(),
+        Some("Bisecting ~4 steps @ a1b2c3d".to_string())
+    );
+
+    // Only one end of the range is known yet
+    let state = GitSpecialState::Bisecting(BisectInfo {
+        bad: 1,
+        candidate: Some("a1b2c3d".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(
+        state.display_name(),
+        Some("Bisecting @ a1b2c3d".to_string())
+    );
 }
 
 #[test]
 fn test_applying_patches_display_name() {
-    let state = GitSpecialState::ApplyingPatches;
+    let state = GitSpecialState::ApplyingPatches(None);
     assert_eq!(state.display_name(), Some("Applying patches".to_string()));
+
+    let state = GitSpecialState::ApplyingPatches(OperationProgress::new(2, 9));
+    assert_eq!(
+        state.display_name(),
+        Some("Applying patches 2/9".to_string())
+    );
+}
+
+#[test]
+fn test_special_state_serialization() {
+    let state = GitSpecialState::Rebasing(RebaseInfo {
+        head_name: Some("feature".to_string()),
+        onto: Some("main".to_string()),
+        progress: OperationProgress::new(3, 7),
+    });
+    assert_eq!(
+        serde_json::to_string(&state).unwrap(),
+        r#"{"kind":"rebasing","details":{"head_name":"feature","onto":"main","progress":{"current":3,"total":7}}}"#
+    );
+    assert_eq!(
+        serde_json::to_string(&GitSpecialState::Normal).unwrap(),
+        r#"{"kind":"normal"}"#
+    );
+}
+
+#[test]
+fn test_git_repo_state_round_trip() {
+    let state = GitRepoState {
+        branch: Some("main".to_string()),
+        ahead: 1,
+        special_state: GitSpecialState::Detached("a1b2c3d".to_string()),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(serde_json::from_str::<GitRepoState>(&json).unwrap(), state);
 }
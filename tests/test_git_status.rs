@@ -0,0 +1,580 @@
+use gprompt::models::git_repo_state::{ChangeCounts, SubmoduleCounts, UpstreamState};
+use gprompt::models::git_special_state::GitSpecialState;
+use gprompt::models::prompt_config::{DescribeStrategy, IgnoreSubmodules};
+use gprompt::models::template::Template;
+use gprompt::services::git_status::{get_git_repo_state, ScanOptions};
+use std::fs;
+use std::path::Path;
+use std::process::Command as StdCommand;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn git(path: &Path, args: &[&str]) {
+    StdCommand::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .unwrap();
+}
+
+fn init_git_repo(path: &Path) {
+    git(path, &["init"]);
+    // Set user config to avoid git warnings
+    git(path, &["config", "user.email", "test@example.com"]);
+    git(path, &["config", "user.name", "Test User"]);
+}
+
+fn commit_file(path: &Path, name: &str, contents: &str) {
+    fs::write(path.join(name), contents).unwrap();
+    git(path, &["add", name]);
+    git(path, &["commit", "-m", &format!("add {name}")]);
+}
+
+#[test]
+fn test_status_counts_from_single_scan() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    commit_file(tmp.path(), "b.txt", "b");
+
+    // Staged, then modified again: counts as both staged and unstaged
+    fs::write(tmp.path().join("a.txt"), "a2").unwrap();
+    git(tmp.path(), &["add", "a.txt"]);
+    fs::write(tmp.path().join("a.txt"), "a3").unwrap();
+    // Unstaged only
+    fs::write(tmp.path().join("b.txt"), "b2").unwrap();
+    // Untracked
+    fs::write(tmp.path().join("c.txt"), "c").unwrap();
+    fs::write(tmp.path().join("d.txt"), "d").unwrap();
+
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.staged, 1);
+    assert_eq!(state.unstaged, 2);
+    assert_eq!(state.untracked, 2);
+}
+
+#[test]
+fn test_clean_repository_has_no_counts() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.staged, 0);
+    assert_eq!(state.unstaged, 0);
+    assert_eq!(state.untracked, 0);
+}
+
+#[test]
+fn test_outside_repository() {
+    let tmp = TempDir::new().unwrap();
+    assert!(get_git_repo_state(tmp.path(), &ScanOptions::default()).is_none());
+}
+
+#[test]
+fn test_conflicted_files_are_counted_separately() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path();
+    init_git_repo(path);
+    commit_file(path, "a.txt", "base a\n");
+    commit_file(path, "b.txt", "base b\n");
+    git(path, &["checkout", "-b", "feature"]);
+    commit_file(path, "a.txt", "feature a\n");
+    commit_file(path, "b.txt", "feature b\n");
+    git(path, &["checkout", "master"]);
+    commit_file(path, "a.txt", "master a\n");
+    commit_file(path, "b.txt", "master b\n");
+    git(path, &["merge", "feature"]);
+    // A clean staged file alongside the conflicts
+    fs::write(path.join("c.txt"), "c").unwrap();
+    git(path, &["add", "c.txt"]);
+
+    let state = get_git_repo_state(path, &ScanOptions::default()).unwrap();
+    assert_eq!(state.conflicted, 2);
+    assert_eq!(state.staged, 1);
+    assert_eq!(state.unstaged, 0);
+}
+
+#[test]
+fn test_status_scan_within_budget() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    fs::write(tmp.path().join("b.txt"), "b").unwrap();
+
+    let options = ScanOptions {
+        status_timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(tmp.path(), &options).unwrap();
+    assert!(!state.status_timed_out);
+    assert_eq!(state.untracked, 1);
+}
+
+#[test]
+fn test_status_scan_out_of_budget_keeps_cheap_data() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    // Enough files that the scan can't finish before the budget is checked
+    for i in 0..5000 {
+        fs::write(tmp.path().join(format!("untracked{i}.txt")), "u").unwrap();
+    }
+
+    let options = ScanOptions {
+        status_timeout: Some(Duration::from_nanos(1)),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(tmp.path(), &options).unwrap();
+    assert!(state.status_timed_out);
+    assert_eq!(state.untracked, 0);
+    assert_eq!(state.branch.as_deref(), Some("master"));
+}
+
+#[test]
+fn test_detached_head_description() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    git(tmp.path(), &["tag", "v1.0"]);
+    git(tmp.path(), &["checkout", "--detach"]);
+
+    // A tag at HEAD is shown even when describing is off
+    let options = ScanOptions {
+        describe: DescribeStrategy::Off,
+        ..Default::default()
+    };
+    let state = get_git_repo_state(tmp.path(), &options).unwrap();
+    assert_eq!(
+        state.special_state,
+        GitSpecialState::Detached("v1.0".to_string())
+    );
+
+    commit_file(tmp.path(), "b.txt", "b");
+    let state = get_git_repo_state(tmp.path(), &options).unwrap();
+    match state.special_state {
+        GitSpecialState::Detached(name) => assert_eq!(name.len(), 7),
+        other => panic!("expected a detached HEAD, got {other:?}"),
+    }
+
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    match state.special_state {
+        GitSpecialState::Detached(name) => assert!(name.starts_with("v1.0-1-g")),
+        other => panic!("expected a detached HEAD, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_short_sha_honours_core_abbrev() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    git(tmp.path(), &["tag", "v1.0"]);
+    commit_file(tmp.path(), "b.txt", "b");
+    git(tmp.path(), &["checkout", "--detach"]);
+    git(tmp.path(), &["config", "core.abbrev", "12"]);
+
+    let expected = StdCommand::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let expected = String::from_utf8_lossy(&expected.stdout).trim().to_string();
+    assert_eq!(expected.len(), 12);
+
+    let options = ScanOptions {
+        describe: DescribeStrategy::Off,
+        ..Default::default()
+    };
+    let state = get_git_repo_state(tmp.path(), &options).unwrap();
+    assert_eq!(
+        state.special_state,
+        GitSpecialState::Detached(expected.clone())
+    );
+
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        state.special_state,
+        GitSpecialState::Detached(format!("v1.0-1-g{expected}"))
+    );
+}
+
+#[test]
+fn test_upstream_state() {
+    let remote = TempDir::new().unwrap();
+    let clone = TempDir::new().unwrap();
+    git(remote.path(), &["init", "--bare"]);
+    init_git_repo(clone.path());
+    commit_file(clone.path(), "a.txt", "a");
+
+    // Nowhere to push to
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.upstream, None);
+
+    // Never pushed
+    git(
+        clone.path(),
+        &["remote", "add", "origin", remote.path().to_str().unwrap()],
+    );
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.upstream, Some(UpstreamState::Missing));
+
+    git(clone.path(), &["push", "-u", "origin", "master"]);
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        state.upstream,
+        Some(UpstreamState::Tracking("origin/master".to_string()))
+    );
+
+    // Deleted on the remote and pruned locally
+    git(clone.path(), &["checkout", "-b", "feature"]);
+    git(clone.path(), &["push", "-u", "origin", "feature"]);
+    git(remote.path(), &["branch", "-D", "feature"]);
+    git(clone.path(), &["fetch", "--prune"]);
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        state.upstream,
+        Some(UpstreamState::Gone("origin/feature".to_string()))
+    );
+
+    // Not on a branch
+    git(clone.path(), &["checkout", "--detach"]);
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.upstream, None);
+}
+
+#[test]
+fn test_push_remote_ahead_behind() {
+    let upstream = TempDir::new().unwrap();
+    let fork = TempDir::new().unwrap();
+    let local = TempDir::new().unwrap();
+    git(upstream.path(), &["init", "--bare"]);
+    git(fork.path(), &["init", "--bare"]);
+    init_git_repo(local.path());
+    git(
+        local.path(),
+        &[
+            "remote",
+            "add",
+            "upstream",
+            upstream.path().to_str().unwrap(),
+        ],
+    );
+    git(
+        local.path(),
+        &["remote", "add", "origin", fork.path().to_str().unwrap()],
+    );
+    commit_file(local.path(), "a.txt", "a");
+    git(local.path(), &["push", "upstream", "master"]);
+    git(local.path(), &["fetch", "upstream"]);
+
+    // Pull from upstream/master, push to origin/feature
+    git(
+        local.path(),
+        &["checkout", "-b", "feature", "--track", "upstream/master"],
+    );
+    git(local.path(), &["config", "remote.pushDefault", "origin"]);
+    commit_file(local.path(), "b.txt", "b");
+    git(local.path(), &["push", "origin", "feature"]);
+    git(local.path(), &["fetch", "origin"]);
+    commit_file(local.path(), "c.txt", "c");
+
+    let state = get_git_repo_state(local.path(), &ScanOptions::default()).unwrap();
+    assert_eq!((state.ahead, state.behind), (2, 0));
+    assert_eq!((state.push_ahead, state.push_behind), (1, 0));
+
+    // Not counted unless the prompt shows them
+    let options = ScanOptions {
+        variables: Some(
+            Template::parse("$git_ahead$git_behind")
+                .unwrap()
+                .variables(),
+        ),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(local.path(), &options).unwrap();
+    assert_eq!((state.ahead, state.behind), (2, 0));
+    assert_eq!((state.push_ahead, state.push_behind), (0, 0));
+
+    // With push.default = upstream, pushing to another remote isn't possible
+    git(local.path(), &["config", "push.default", "upstream"]);
+    let state = get_git_repo_state(local.path(), &ScanOptions::default()).unwrap();
+    assert_eq!((state.push_ahead, state.push_behind), (0, 0));
+
+    // When pushing to the upstream, the counters aren't repeated
+    git(local.path(), &["config", "--unset", "remote.pushDefault"]);
+    let state = get_git_repo_state(local.path(), &ScanOptions::default()).unwrap();
+    assert_eq!((state.ahead, state.behind), (2, 0));
+    assert_eq!((state.push_ahead, state.push_behind), (0, 0));
+}
+
+#[test]
+fn test_default_branch_divergence() {
+    let remote = TempDir::new().unwrap();
+    let seed = TempDir::new().unwrap();
+    let clone = TempDir::new().unwrap();
+    git(remote.path(), &["init", "--bare"]);
+    init_git_repo(seed.path());
+    commit_file(seed.path(), "a.txt", "a");
+    let remote_path = remote.path().to_str().unwrap();
+    git(seed.path(), &["push", remote_path, "master"]);
+
+    // `git clone` sets origin/HEAD
+    git(clone.path(), &["clone", remote_path, "."]);
+    init_git_repo(clone.path());
+    git(clone.path(), &["checkout", "-b", "feature"]);
+    commit_file(clone.path(), "b.txt", "b");
+    commit_file(clone.path(), "c.txt", "c");
+    commit_file(seed.path(), "d.txt", "d");
+    git(seed.path(), &["push", remote_path, "master"]);
+    git(clone.path(), &["fetch"]);
+
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    let default_branch = state.default_branch.unwrap();
+    assert_eq!(default_branch.name, "origin/master");
+    assert_eq!((default_branch.ahead, default_branch.behind), (2, 1));
+
+    // Not looked up unless the prompt shows it
+    let options = ScanOptions {
+        variables: Some(
+            Template::parse("$git_branch $git_status")
+                .unwrap()
+                .variables(),
+        ),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(clone.path(), &options).unwrap();
+    assert!(state.default_branch.is_none());
+    let options = ScanOptions {
+        variables: Some(Template::parse("($git_default)").unwrap().variables()),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(clone.path(), &options).unwrap();
+    assert!(state.default_branch.is_some());
+
+    // Without origin/HEAD the default branch is unknown
+    git(clone.path(), &["remote", "set-head", "origin", "--delete"]);
+    let state = get_git_repo_state(clone.path(), &ScanOptions::default()).unwrap();
+    assert!(state.default_branch.is_none());
+}
+
+#[test]
+fn test_linked_worktree() {
+    let dir = TempDir::new().unwrap();
+    let main = dir.path().join("gprompt");
+    fs::create_dir(&main).unwrap();
+    init_git_repo(&main);
+    commit_file(&main, "a.txt", "a");
+
+    let state = get_git_repo_state(&main, &ScanOptions::default()).unwrap();
+    assert!(state.worktree.is_none());
+    assert_eq!(state.prunable_worktrees, 0);
+
+    git(
+        &main,
+        &["worktree", "add", "-b", "hotfix", "../trees/hotfix"],
+    );
+    let linked = dir.path().join("trees").join("hotfix");
+    fs::create_dir(linked.join("src")).unwrap();
+    let state = get_git_repo_state(&linked.join("src"), &ScanOptions::default()).unwrap();
+    let worktree = state.worktree.unwrap();
+    assert_eq!(worktree.name, "hotfix");
+    assert_eq!(worktree.main_path.as_deref(), Some("../../gprompt"));
+    assert!(!worktree.locked);
+    assert_eq!(state.branch.as_deref(), Some("hotfix"));
+
+    git(&main, &["worktree", "lock", "../trees/hotfix"]);
+    let state = get_git_repo_state(&linked, &ScanOptions::default()).unwrap();
+    assert!(state.worktree.unwrap().locked);
+
+    // A deleted worktree can be pruned, unless it is locked
+    git(&main, &["worktree", "add", "../trees/spike"]);
+    fs::remove_dir_all(dir.path().join("trees").join("spike")).unwrap();
+    let state = get_git_repo_state(&main, &ScanOptions::default()).unwrap();
+    assert_eq!(state.prunable_worktrees, 1);
+    let state = get_git_repo_state(&linked, &ScanOptions::default()).unwrap();
+    assert_eq!(state.prunable_worktrees, 1);
+}
+
+#[test]
+fn test_submodules() {
+    let dir = TempDir::new().unwrap();
+    let library = dir.path().join("library");
+    let app = dir.path().join("app");
+    for repo in [&library, &app] {
+        fs::create_dir(repo).unwrap();
+        init_git_repo(repo);
+        commit_file(repo, "a.txt", "a");
+    }
+    let library_path = library.to_str().unwrap();
+    for name in ["lib", "vendor", "third_party"] {
+        git(
+            &app,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                library_path,
+                name,
+            ],
+        );
+    }
+    git(&app, &["commit", "-m", "add submodules"]);
+
+    let state = get_git_repo_state(&app, &ScanOptions::default()).unwrap();
+    assert_eq!(state.submodules, SubmoduleCounts::default());
+    assert!(state.superproject.is_none());
+
+    // Inside a submodule, the superproject is named
+    let state = get_git_repo_state(&app.join("lib"), &ScanOptions::default()).unwrap();
+    assert_eq!(state.superproject.as_deref(), Some("app"));
+    let options = ScanOptions {
+        variables: Some(Template::parse("$git_branch").unwrap().variables()),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(&app.join("lib"), &options).unwrap();
+    assert!(state.superproject.is_none());
+
+    let submodule = app.join("lib");
+    init_git_repo(&submodule);
+    commit_file(&submodule, "b.txt", "b");
+    fs::write(app.join("vendor").join("c.txt"), "c").unwrap();
+    git(&app, &["submodule", "deinit", "third_party"]);
+    let state = get_git_repo_state(&app, &ScanOptions::default()).unwrap();
+    let expected = SubmoduleCounts {
+        uninitialized: 1,
+        new_commits: 1,
+        dirty: 1,
+    };
+    assert_eq!(state.submodules, expected);
+    // The changed submodules are unstaged changes in the superproject too
+    assert_eq!(state.unstaged, 2);
+
+    // Not counted unless the prompt shows them
+    let options = ScanOptions {
+        variables: Some(Template::parse("$git_unstaged").unwrap().variables()),
+        ..Default::default()
+    };
+    let state = get_git_repo_state(&app, &options).unwrap();
+    assert_eq!(state.submodules, SubmoduleCounts::default());
+    assert_eq!(state.unstaged, 2);
+
+    // Untracked files in submodules can be ignored, as with diff.ignoreSubmodules
+    let options = ScanOptions {
+        ignore_submodules: IgnoreSubmodules::Untracked,
+        ..Default::default()
+    };
+    let state = get_git_repo_state(&app, &options).unwrap();
+    assert_eq!(state.submodules.dirty, 0);
+    let options = ScanOptions {
+        ignore_submodules: IgnoreSubmodules::Dirty,
+        ..Default::default()
+    };
+    let state = get_git_repo_state(&app, &options).unwrap();
+    assert_eq!(state.submodules.dirty, 0);
+    assert_eq!(state.submodules.new_commits, 1);
+    assert_eq!(state.unstaged, 1);
+    git(&app, &["config", "diff.ignoreSubmodules", "all"]);
+    let state = get_git_repo_state(&app, &ScanOptions::default()).unwrap();
+    assert_eq!(state.submodules, SubmoduleCounts::default());
+    assert_eq!(state.unstaged, 0);
+}
+
+#[test]
+fn test_git_config_opt_outs() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    fs::write(tmp.path().join("a.txt"), "changed").unwrap();
+    fs::write(tmp.path().join("new.txt"), "new").unwrap();
+
+    git(tmp.path(), &["config", "gprompt.showUntracked", "false"]);
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!((state.unstaged, state.untracked), (1, 0));
+
+    // Without the scan the file counts are left at zero, but aren't unknown
+    git(tmp.path(), &["config", "gprompt.showStatus", "false"]);
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!((state.unstaged, state.untracked), (0, 0));
+    assert!(!state.status_timed_out);
+    assert_eq!(state.branch.as_deref(), Some("master"));
+
+    git(tmp.path(), &["config", "gprompt.disable", "true"]);
+    assert!(get_git_repo_state(tmp.path(), &ScanOptions::default()).is_none());
+}
+
+#[test]
+fn test_show_untracked_files() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    commit_file(tmp.path(), "a.txt", "a");
+    fs::write(tmp.path().join("new.txt"), "new").unwrap();
+    let build = tmp.path().join("build");
+    fs::create_dir_all(build.join("out")).unwrap();
+    fs::write(build.join("one.o"), "1").unwrap();
+    fs::write(build.join("out").join("two.o"), "2").unwrap();
+
+    // An untracked directory counts once, as in `git status`
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.untracked, 2);
+
+    git(tmp.path(), &["config", "status.showUntrackedFiles", "all"]);
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.untracked, 3);
+
+    git(tmp.path(), &["config", "status.showUntrackedFiles", "no"]);
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.untracked, 0);
+}
+
+#[test]
+fn test_changes_by_kind() {
+    let tmp = TempDir::new().unwrap();
+    init_git_repo(tmp.path());
+    let contents = "some text long enough for rename detection to match\n".repeat(10);
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt", "e.txt"] {
+        commit_file(tmp.path(), name, &contents);
+    }
+
+    git(tmp.path(), &["mv", "a.txt", "moved.txt"]);
+    git(tmp.path(), &["rm", "-q", "b.txt"]);
+    fs::write(tmp.path().join("new.txt"), "new").unwrap();
+    git(tmp.path(), &["add", "new.txt"]);
+    fs::write(tmp.path().join("c.txt"), "changed").unwrap();
+    fs::remove_file(tmp.path().join("d.txt")).unwrap();
+
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    let staged = ChangeCounts {
+        added: 1,
+        deleted: 1,
+        renamed: 1,
+        ..Default::default()
+    };
+    assert_eq!(state.staged_changes, staged);
+    assert_eq!(state.staged, 3);
+    let unstaged = ChangeCounts {
+        modified: 1,
+        deleted: 1,
+        ..Default::default()
+    };
+    assert_eq!(state.unstaged_changes, unstaged);
+    assert_eq!(state.unstaged, 2);
+
+    // A rename with edits is still a rename
+    git(tmp.path(), &["mv", "e.txt", "edited.txt"]);
+    let edited = format!("{contents}one more line\n");
+    fs::write(tmp.path().join("edited.txt"), edited).unwrap();
+    git(tmp.path(), &["add", "edited.txt"]);
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.staged_changes.renamed, 2);
+    assert_eq!(state.staged_changes.modified, 0);
+
+    // As in `git status`, a file moved without telling git is a deletion and an
+    // untracked file
+    fs::rename(tmp.path().join("c.txt"), tmp.path().join("f.txt")).unwrap();
+    let state = get_git_repo_state(tmp.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(state.unstaged_changes.renamed, 0);
+    assert_eq!(state.unstaged_changes.deleted, 2);
+    assert_eq!(state.untracked, 1);
+}
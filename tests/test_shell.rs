@@ -0,0 +1,104 @@
+use gprompt::models::git_repo_state::GitRepoState;
+use gprompt::models::prompt_config::PromptConfig;
+use gprompt::models::shell::{Shell, ShellState};
+use gprompt::models::template::Template;
+use gprompt::services::prompt_renderer::PromptContext;
+use gprompt::services::shell_init::init_script;
+
+#[test]
+fn test_shell_from_name() {
+    assert_eq!(Shell::from_name("bash"), Some(Shell::Bash));
+    assert_eq!(Shell::from_name("zsh"), Some(Shell::Zsh));
+    assert_eq!(Shell::from_name("fish"), Some(Shell::Fish));
+    assert_eq!(Shell::from_name("plain"), Some(Shell::Plain));
+    assert_eq!(Shell::from_name("tcsh"), None);
+}
+
+#[test]
+fn test_wrap_non_printing() {
+    let code = "\x1b[34m";
+    assert_eq!(Shell::Bash.wrap_non_printing(code), "\\[\x1b[34m\\]");
+    assert_eq!(Shell::Zsh.wrap_non_printing(code), "%{\x1b[34m%}");
+    assert_eq!(Shell::Fish.wrap_non_printing(code), code);
+    assert_eq!(Shell::Plain.wrap_non_printing(code), code);
+    // Nothing to wrap for unstyled text
+    assert_eq!(Shell::Bash.wrap_non_printing(""), "");
+}
+
+#[test]
+fn test_bash_escape() {
+    assert_eq!(Shell::Bash.escape("feature/x"), "feature/x");
+    assert_eq!(Shell::Bash.escape("a$b"), "a\\\\$b");
+    assert_eq!(Shell::Bash.escape("a`b`"), "a\\\\`b\\\\`");
+    assert_eq!(Shell::Bash.escape("a\\b"), "a\\\\\\\\b");
+    // `%` is not special in PS1
+    assert_eq!(Shell::Bash.escape("100%"), "100%");
+}
+
+#[test]
+fn test_zsh_escape() {
+    assert_eq!(Shell::Zsh.escape("100%"), "100%%");
+    assert_eq!(Shell::Zsh.escape("%n%~"), "%%n%%~");
+}
+
+#[test]
+fn test_fish_and_plain_do_not_escape() {
+    for shell in [Shell::Fish, Shell::Plain] {
+        assert_eq!(shell.escape("$a %b \\c"), "$a %b \\c");
+    }
+}
+
+#[test]
+fn test_render_escapes_values_but_not_template_text() {
+    let config = PromptConfig::default();
+    let state = GitRepoState {
+        branch: Some("fix-100%".to_string()),
+        ..Default::default()
+    };
+    let context = PromptContext {
+        config: &config,
+        path: "~/50%",
+        git_state: Some(&state),
+        shell: Shell::Zsh,
+        shell_state: ShellState::default(),
+    };
+    let rendered = context.render(&Template::parse("%n $path $git_branch").unwrap());
+    assert_eq!(
+        rendered,
+        "%n %{\x1b[34m%}~/50%%%{\x1b[0m%} %{\x1b[2;37m%}fix-100%%%{\x1b[0m%}"
+    );
+}
+
+#[test]
+fn test_init_script_quotes_executable() {
+    let script = init_script(Shell::Bash, "/opt/it's/gprompt", false).unwrap();
+    assert!(script.contains("PS1=\"$('/opt/it'\\''s/gprompt' --shell bash"));
+    assert!(!script.contains("::GPROMPT::"));
+
+    let script = init_script(Shell::Fish, "/opt/it's/gprompt", false).unwrap();
+    assert!(script.contains("'/opt/it\\'s/gprompt' --shell fish"));
+
+    // zsh's prompts are single-quoted, so the path goes in a variable outside them
+    let script = init_script(Shell::Zsh, "/opt/my tools/it's/gprompt", true).unwrap();
+    assert!(script.contains("\n__gprompt_bin='/opt/my tools/it'\\''s/gprompt'\n"));
+    assert!(script.contains("PROMPT='$(\"$__gprompt_bin\" \"${__gprompt_args[@]}\")'"));
+    assert!(script.contains("RPROMPT='$(\"$__gprompt_bin\" \"${__gprompt_args[@]}\" --right)'"));
+    assert!(!script.contains("::GPROMPT::"));
+}
+
+#[test]
+fn test_init_script_right_prompt() {
+    let script = init_script(Shell::Zsh, "gprompt", false).unwrap();
+    assert!(!script.contains("RPROMPT"));
+    let script = init_script(Shell::Zsh, "gprompt", true).unwrap();
+    assert!(script.contains("RPROMPT="));
+
+    let script = init_script(Shell::Fish, "gprompt", true).unwrap();
+    assert!(script.contains("function fish_right_prompt"));
+
+    // bash has no right-hand prompt
+    assert_eq!(
+        init_script(Shell::Bash, "gprompt", true),
+        init_script(Shell::Bash, "gprompt", false)
+    );
+}
@@ -0,0 +1,444 @@
+use gprompt::models::git_repo_state::{
+    ChangeCounts, DefaultBranch, GitRepoState, SubmoduleCounts, UpstreamState, WorktreeInfo,
+};
+use gprompt::models::git_special_state::{GitSpecialState, OperationProgress, RebaseInfo};
+use gprompt::models::prompt_config::{ColourSpec, PromptConfig, StyleAttributes};
+use gprompt::models::shell::{Shell, ShellState};
+use gprompt::models::template::{Segment, Template, TemplateError, Variable};
+use gprompt::services::prompt_renderer::{abbreviate_path, format_duration, PromptContext};
+
+/// Config with all colours and styles removed, so rendered output is plain text
+fn plain_config() -> PromptConfig {
+    let mut config = PromptConfig::default();
+    let status = &mut config.git_status;
+    config.path.colour = ColourSpec::Default;
+    config.duration.colour = ColourSpec::Default;
+    for element in [
+        &mut config.git_branch,
+        &mut config.status,
+        &mut config.jobs,
+        &mut config.character,
+        &mut config.git_upstream,
+        &mut config.git_worktree,
+        &mut config.git_main_worktree,
+        &mut config.git_superproject,
+        &mut status.ahead,
+        &mut status.behind,
+        &mut status.push_ahead,
+        &mut status.push_behind,
+        &mut status.default_ahead,
+        &mut status.default_behind,
+        &mut status.unstaged,
+        &mut status.staged,
+        &mut status.unstaged_modified,
+        &mut status.unstaged_deleted,
+        &mut status.unstaged_typechanged,
+        &mut status.staged_added,
+        &mut status.staged_modified,
+        &mut status.staged_deleted,
+        &mut status.staged_renamed,
+        &mut status.staged_typechanged,
+        &mut status.stashed,
+        &mut status.untracked,
+        &mut status.conflicted,
+        &mut status.unknown,
+        &mut status.gone,
+        &mut status.no_upstream,
+        &mut status.locked,
+        &mut status.prunable,
+        &mut status.submodules,
+    ] {
+        element.colour = ColourSpec::Default;
+        element.style = StyleAttributes::default();
+    }
+    config
+}
+
+fn render(format: &str, git_state: Option<&GitRepoState>) -> String {
+    render_with_shell_state(format, git_state, ShellState::default())
+}
+
+fn render_with_shell_state(
+    format: &str,
+    git_state: Option<&GitRepoState>,
+    shell_state: ShellState,
+) -> String {
+    let mut config = plain_config();
+    config.path.abbreviate = true;
+    let context = PromptContext {
+        config: &config,
+        path: "~/src/rust/gprompt",
+        git_state,
+        shell: Shell::Plain,
+        shell_state,
+    };
+    context.render(&Template::parse(format).unwrap())
+}
+
+#[test]
+fn test_parse_text_and_variables() {
+    let template = Template::parse("in $path!").unwrap();
+    assert_eq!(
+        template.segments(),
+        &[
+            Segment::Text("in ".to_string()),
+            Segment::Variable(Variable::Path),
+            Segment::Text("!".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_nested_groups() {
+    let template = Template::parse("($git_branch( $git_status))").unwrap();
+    assert_eq!(
+        template.segments(),
+        &[Segment::Group(vec![
+            Segment::Variable(Variable::GitBranch),
+            Segment::Group(vec![
+                Segment::Text(" ".to_string()),
+                Segment::Variable(Variable::GitStatus),
+            ]),
+        ])]
+    );
+}
+
+#[test]
+fn test_parse_escapes() {
+    let template = Template::parse(r"\$path \(x\) \\").unwrap();
+    assert_eq!(
+        template.segments(),
+        &[Segment::Text(r"$path (x) \".to_string())]
+    );
+}
+
+#[test]
+fn test_variables_include_parts_of_composites() {
+    let variables = Template::parse("$path( $git_status)").unwrap().variables();
+    assert!(variables.contains(&Variable::Path));
+    assert!(variables.contains(&Variable::GitStatus));
+    assert!(variables.contains(&Variable::GitPushAhead));
+    assert!(!variables.contains(&Variable::GitDefaultAhead));
+
+    let variables = Template::parse("$git_default").unwrap().variables();
+    assert!(variables.contains(&Variable::GitDefaultBehind));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        Template::parse("$nope"),
+        Err(TemplateError::UnknownVariable("nope".to_string()))
+    );
+    assert_eq!(
+        Template::parse("$ x"),
+        Err(TemplateError::MissingVariableName)
+    );
+    assert_eq!(Template::parse("($path"), Err(TemplateError::UnclosedGroup));
+    assert_eq!(
+        Template::parse("$path)"),
+        Err(TemplateError::UnexpectedGroupEnd)
+    );
+    assert_eq!(Template::parse("x\\"), Err(TemplateError::TrailingEscape));
+}
+
+#[test]
+fn test_default_format_outside_repository() {
+    assert_eq!(render(&default_format(), None), "\n~/src/rust/gprompt \n❯ ");
+}
+
+#[test]
+fn test_default_format_in_repository() {
+    let state = GitRepoState {
+        branch: Some("main".to_string()),
+        ahead: 1,
+        untracked: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        render(&default_format(), Some(&state)),
+        "\n~/src/rust/gprompt main ↑*\n❯ "
+    );
+}
+
+#[test]
+fn test_group_collapses_when_empty() {
+    let format = "$path( on $git_branch)( [$git_status])";
+    assert_eq!(render(format, None), "~/src/rust/gprompt");
+
+    let state = GitRepoState {
+        branch: Some("main".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(render(format, Some(&state)), "~/src/rust/gprompt on main");
+}
+
+#[test]
+fn test_special_state_replaces_branch() {
+    let state = GitRepoState {
+        branch: Some("feature".to_string()),
+        special_state: GitSpecialState::Rebasing(RebaseInfo {
+            progress: OperationProgress::new(2, 5),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert_eq!(render("$git_branch", Some(&state)), "Rebasing 2/5");
+}
+
+#[test]
+fn test_individual_indicators() {
+    let state = GitRepoState {
+        staged: 1,
+        stashed: 1,
+        ..Default::default()
+    };
+    assert_eq!(
+        render("$git_stashed|$git_staged|$git_ahead", Some(&state)),
+        "•|+|"
+    );
+}
+
+#[test]
+fn test_push_indicators_follow_upstream_ones() {
+    let state = GitRepoState {
+        ahead: 2,
+        push_ahead: 1,
+        push_behind: 3,
+        ..Default::default()
+    };
+    assert_eq!(render("$git_status", Some(&state)), "↑⇡⇣");
+    assert_eq!(render("$git_push_behind", Some(&state)), "⇣");
+}
+
+fn default_format() -> String {
+    PromptConfig::default().format.source().to_string()
+}
+
+#[test]
+fn test_shell_state_variables() {
+    let format = "($status )($duration )($jobs )$path";
+    assert_eq!(render(format, None), "~/src/rust/gprompt");
+
+    let shell_state = ShellState {
+        status: Some(0),
+        duration_ms: Some(1999),
+        jobs: Some(0),
+        width: None,
+    };
+    assert_eq!(
+        render_with_shell_state(format, None, shell_state),
+        "~/src/rust/gprompt"
+    );
+
+    let shell_state = ShellState {
+        status: Some(130),
+        duration_ms: Some(65_000),
+        jobs: Some(2),
+        width: Some(30),
+    };
+    assert_eq!(
+        render_with_shell_state(format, None, shell_state),
+        "✘130 1m5s ✦2 ~/s/r/gprompt"
+    );
+}
+
+#[test]
+fn test_path_is_abbreviated_only_when_too_wide() {
+    let shell_state = ShellState {
+        width: Some(120),
+        ..Default::default()
+    };
+    assert_eq!(
+        render_with_shell_state("$path", None, shell_state),
+        "~/src/rust/gprompt"
+    );
+}
+
+#[test]
+fn test_abbreviate_path() {
+    assert_eq!(abbreviate_path("~/.config/gprompt"), "~/.c/gprompt");
+    assert_eq!(abbreviate_path("/usr/local/bin"), "/u/l/bin");
+    assert_eq!(abbreviate_path("~"), "~");
+    assert_eq!(abbreviate_path("/"), "/");
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(850), "850ms");
+    assert_eq!(format_duration(4_200), "4s");
+    assert_eq!(format_duration(125_000), "2m5s");
+    assert_eq!(format_duration(3_723_000), "1h2m3s");
+}
+
+#[test]
+fn test_indicator_counts_and_separator() {
+    let mut config = plain_config();
+    config.git_status.separator = " ".to_string();
+    for indicator in [
+        &mut config.git_status.ahead,
+        &mut config.git_status.behind,
+        &mut config.git_status.staged,
+    ] {
+        indicator.show_count = true;
+    }
+    let state = GitRepoState {
+        ahead: 3,
+        behind: 1,
+        staged: 2,
+        unstaged: 5,
+        ..Default::default()
+    };
+    let context = PromptContext {
+        config: &config,
+        path: "~",
+        git_state: Some(&state),
+        shell: Shell::Plain,
+        shell_state: ShellState::default(),
+    };
+    let template = Template::parse("[$git_status]").unwrap();
+    assert_eq!(context.render(&template), "[↑3 ↓1 × +2]");
+}
+
+#[test]
+fn test_unknown_status_marker() {
+    let state = GitRepoState {
+        branch: Some("main".to_string()),
+        ahead: 1,
+        status_timed_out: true,
+        ..Default::default()
+    };
+    assert_eq!(render("$git_branch $git_status", Some(&state)), "main ↑?");
+}
+
+#[test]
+fn test_upstream_name_and_indicators() {
+    let format = "$git_branch($git_upstream) $git_status";
+    let mut state = GitRepoState {
+        branch: Some("main".to_string()),
+        upstream: Some(UpstreamState::Tracking("origin/main".to_string())),
+        ..Default::default()
+    };
+    assert_eq!(render(format, Some(&state)), "main…origin/main ");
+
+    state.upstream = Some(UpstreamState::Gone("origin/main".to_string()));
+    assert_eq!(render(format, Some(&state)), "main ⊘");
+
+    state.upstream = Some(UpstreamState::Missing);
+    assert_eq!(render(format, Some(&state)), "main ∅");
+
+    // An empty symbol hides it
+    let mut config = plain_config();
+    config.git_status.no_upstream.symbol = String::new();
+    let context = PromptContext {
+        config: &config,
+        path: "~",
+        git_state: Some(&state),
+        shell: Shell::Plain,
+        shell_state: ShellState::default(),
+    };
+    assert_eq!(context.render(&Template::parse(format).unwrap()), "main ");
+}
+
+#[test]
+fn test_default_branch_divergence() {
+    let mut state = GitRepoState {
+        branch: Some("feature".to_string()),
+        default_branch: Some(DefaultBranch {
+            name: "origin/main".to_string(),
+            ahead: 2,
+            behind: 5,
+        }),
+        ..Default::default()
+    };
+    // Not part of $git_status
+    assert_eq!(render("$git_branch $git_status", Some(&state)), "feature ");
+    assert_eq!(
+        render("$git_branch( $git_default)", Some(&state)),
+        "feature ▴2▾5"
+    );
+
+    state.default_branch = None;
+    assert_eq!(
+        render("$git_branch( $git_default)", Some(&state)),
+        "feature"
+    );
+}
+
+#[test]
+fn test_worktree_variables() {
+    let format = "$git_branch( $git_worktree)( $git_main_worktree)( $git_status)";
+    let mut state = GitRepoState {
+        branch: Some("hotfix".to_string()),
+        worktree: Some(WorktreeInfo {
+            name: "hotfix".to_string(),
+            main_path: Some("../gprompt".to_string()),
+            locked: true,
+        }),
+        prunable_worktrees: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        render(format, Some(&state)),
+        "hotfix ⎇ hotfix ⌂../gprompt ⊡✂"
+    );
+
+    // The main worktree shows neither name nor path
+    state.worktree = None;
+    state.prunable_worktrees = 0;
+    assert_eq!(render(format, Some(&state)), "hotfix");
+}
+
+#[test]
+fn test_submodule_variables() {
+    let format = "($git_superproject )$git_branch( $git_status)";
+    let mut state = GitRepoState {
+        branch: Some("main".to_string()),
+        superproject: Some("app".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(render(format, Some(&state)), "⊂app main");
+
+    state.superproject = None;
+    state.submodules = SubmoduleCounts {
+        uninitialized: 1,
+        new_commits: 1,
+        dirty: 0,
+    };
+    assert_eq!(render(format, Some(&state)), "main ◫");
+}
+
+#[test]
+fn test_change_kind_indicators() {
+    let state = GitRepoState {
+        staged: 2,
+        staged_changes: ChangeCounts {
+            modified: 1,
+            deleted: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    // Hidden unless given a symbol
+    assert_eq!(render("$git_status", Some(&state)), "+");
+
+    let mut config = plain_config();
+    config.git_status.staged.symbol = String::new();
+    config.git_status.staged_modified.symbol = "+".to_string();
+    config.git_status.staged_deleted.symbol = "✘".to_string();
+    let context = PromptContext {
+        config: &config,
+        path: "~",
+        git_state: Some(&state),
+        shell: Shell::Plain,
+        shell_state: ShellState::default(),
+    };
+    assert_eq!(
+        context.render(&Template::parse("$git_status").unwrap()),
+        "+✘"
+    );
+    assert_eq!(
+        context.render(&Template::parse("$git_staged_deleted").unwrap()),
+        "✘"
+    );
+}